@@ -0,0 +1,76 @@
+// Broad phase against brute force at 1k asteroids. Run with
+// `cargo bench --bench collision`.
+extern crate rand;
+extern crate vecmath;
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/collider.rs"]
+mod collider;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+use collider::{Collider, SpatialGrid};
+
+const ASTEROIDS: usize = 1000;
+const PROJECTILES: usize = 200;
+const FIELD: f64 = 4000.0;
+const ROUNDS: u32 = 200;
+
+fn field(rng: &mut StdRng, n: usize, radii: &[f64]) -> Vec<Collider> {
+    (0..n)
+        .map(|i| {
+            let p = [rng.gen_range(0.0..FIELD), rng.gen_range(0.0..FIELD)];
+            Collider::new(p, radii[i % radii.len()])
+        })
+        .collect()
+}
+
+fn brute_force(asteroids: &[Collider], projectiles: &[Collider]) -> usize {
+    let mut hits = 0;
+    for a in asteroids {
+        for p in projectiles {
+            if p.are_colliding(a) {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+// includes building the grid, the game rebuilds it every step
+fn grid(asteroids: &[Collider], projectiles: &[Collider]) -> usize {
+    let grid = SpatialGrid::build(asteroids.iter());
+    let mut hits = 0;
+    for p in projectiles {
+        for i in grid.candidates(p) {
+            if p.are_colliding(&asteroids[i]) {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+fn time<F: FnMut() -> usize>(name: &str, mut f: F) -> usize {
+    let mut hits = 0;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        hits = f();
+    }
+    let per_round: Duration = start.elapsed() / ROUNDS;
+    println!("{:<12} {:>10.1?} per step, {} hits", name, per_round, hits);
+    hits
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let asteroids = field(&mut rng, ASTEROIDS, &[25.0, 40.0, 60.0]);
+    let projectiles = field(&mut rng, PROJECTILES, &[2.0]);
+
+    println!("{} asteroids, {} projectiles", ASTEROIDS, PROJECTILES);
+    let brute = time("brute force", || brute_force(&asteroids, &projectiles));
+    let grid = time("grid", || grid(&asteroids, &projectiles));
+    assert_eq!(brute, grid, "grid missed or invented collisions");
+}
@@ -3,32 +3,106 @@
 // use piston::*;
 use graphics::*;
 use opengl_graphics::GlGraphics;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::f64::consts::PI;
 
-use collider::Collider;
+use collider::{convex_hull, Collider, SpatialGrid};
 use movable_body::MovableBody;
 use proc::*;
 use traits::*;
 
 use crate::movable_body::BodyState;
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum AsteroidSizeClass {
     Small,
     Medium,
     Large,
 }
 
+impl AsteroidSizeClass {
+    // widest the asteroid's outline can be
+    pub fn size(&self) -> f64 {
+        match self {
+            AsteroidSizeClass::Small => 40.0,
+            AsteroidSizeClass::Medium => 64.0,
+            AsteroidSizeClass::Large => 96.0,
+        }
+    }
+
+    pub fn for_proc(proc: &Proc, sizing: &SizeThresholds) -> AsteroidSizeClass {
+        let usage = match sizing.metric {
+            SizeMetric::Memory => proc.rss as f64,
+            SizeMetric::CpuTime => proc.cpu_time.as_secs_f64(),
+        };
+
+        if usage >= sizing.large {
+            AsteroidSizeClass::Large
+        } else if usage >= sizing.medium {
+            AsteroidSizeClass::Medium
+        } else {
+            AsteroidSizeClass::Small
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum SizeMetric {
+    Memory,  // resident memory in bytes
+    CpuTime, // total cpu seconds
+}
+
+// usage at or above which a process becomes a medium or large asteroid
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct SizeThresholds {
+    pub metric: SizeMetric,
+    pub medium: f64,
+    pub large: f64,
+}
+
+impl SizeThresholds {
+    pub fn new(metric: SizeMetric) -> SizeThresholds {
+        match metric {
+            SizeMetric::Memory => SizeThresholds {
+                metric,
+                medium: 100.0 * 1024.0 * 1024.0,
+                large: 500.0 * 1024.0 * 1024.0,
+            },
+            SizeMetric::CpuTime => SizeThresholds {
+                metric,
+                medium: 60.0,
+                large: 600.0,
+            },
+        }
+    }
+}
+
+impl Default for SizeThresholds {
+    fn default() -> SizeThresholds {
+        SizeThresholds::new(SizeMetric::Memory)
+    }
+}
+
 pub struct Asteroid {
-    id: u32, // this will be the corresponding pid
+    pub id: u32, // this will be the corresponding pid
     size: f64,
     size_class: AsteroidSizeClass,
+    fragment: bool,         // a scattered piece of a process with no children
+    outline: Vec<[f64; 2]>, // unit sized, see `outline`
     pub c: Collider,
     pub b: MovableBody,
 }
 
 const ASTEROID_SPLIT_FACTOR: u32 = 4;
 const ASTEROID_SPLIT_VARIANCE: f64 = 2.0;
+const ASTEROID_SPLIT_SPEED: f64 = 60.0;
+
+// corners on an asteroid's outline, and how far in they can be pulled
+const OUTLINE_VERTICES: std::ops::RangeInclusive<usize> = 9..=14;
+const OUTLINE_DENT: f64 = 0.3;
 
 const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 0.8];
 const C3: [f32; 4] = [0.5, 0.5, 0.0, 0.4];
@@ -39,28 +113,34 @@ impl GameObject for Asteroid {
 
         self.b.update(dt);
         self.c.p = self.b.p;
+        self.c.angle = self.b.r;
     }
     fn render(&self, ctx: &Context, gl: &mut GlGraphics) {
-        if (self.b.state == BodyState::Dead) {
+        if self.b.state == BodyState::Dead {
             return;
         }
-        // let verts: [Vector2<f64>; 4] = [[0.0, 0.0], [0.0, 20.0], [20.0, 20.0], [20.0, 0.0]];
+        let half = self.size / 2.0;
         let transform = ctx
             .transform
             .trans(self.b.p[0], self.b.p[1])
             .rot_rad(self.b.r)
-            .trans(-20.0, -20.0);
-
-        // Draw a box rotating around the middle of the screen.
-        rectangle(BLACK, [0.0, 0.0, 40.0, 40.0], transform, gl);
+            .scale(half, half);
 
-        // polygon(RED, &verts[..], transform, gl)
+        // the outline has dents so it's drawn as a fan of triangles from
+        // the centre rather than one polygon
+        let n = self.outline.len();
+        for (i, v) in self.outline.iter().enumerate() {
+            let w = self.outline[(i + 1) % n];
+            polygon(BLACK, &[[0.0, 0.0], *v, w], transform, gl);
+        }
     }
     fn render_debug(&self, ctx: &Context, gl: &mut GlGraphics) {
-        if (self.b.state == BodyState::Dead) {
+        if self.b.state == BodyState::Dead {
             return;
         }
 
+        // the bounding circle the broad phase sees, and the outline that
+        // is actually collided with
         let transform = ctx.transform.trans(self.b.p[0], self.b.p[1]);
         circle_arc(
             C3,
@@ -70,26 +150,25 @@ impl GameObject for Asteroid {
             [0.0, 0.0, self.c.r * 2.0, self.c.r * 2.0],
             transform.trans(-self.c.r, -self.c.r),
             gl,
-        )
+        );
+
+        let vertices = self.c.vertices();
+        for (i, v) in vertices.iter().enumerate() {
+            let w = vertices[(i + 1) % vertices.len()];
+            line(C3, 0.5, [v[0], v[1], w[0], w[1]], ctx.transform, gl);
+        }
     }
 }
 
 impl Collides for Asteroid {
-    fn collides_with<C: Collides>(&self, other: &C) -> bool {
-        self.c.are_colliding(other.get_collider())
-    }
-
     fn get_collider(&self) -> &Collider {
         &self.c
     }
 
-    fn on_collision<C: Collides>(&mut self, other: &C) {
-        let t = other.collider_type();
-        match (t) {
-            ColliderType::Asteroid => (), // trigger end game,
-            _ => (),
-        }
-    }
+    // exploding needs the process tree, so that is left to
+    // `collision::resolve_collisions`, and bouncing off another asteroid
+    // needs both bodies, see `collision::bounce_asteroids`
+    fn on_collision<C: Collides>(&mut self, _other: &C) {}
 
     fn collider_type(&self) -> ColliderType {
         ColliderType::Asteroid
@@ -97,114 +176,540 @@ impl Collides for Asteroid {
 }
 
 impl Asteroid {
-    pub fn new(
-        body: MovableBody,
-        collider: Collider,
-        size_class: AsteroidSizeClass,
-        pid: u32,
-    ) -> Asteroid {
-        Asteroid {
+    pub fn new(body: MovableBody, size_class: AsteroidSizeClass, pid: u32) -> Asteroid {
+        let mut a = Asteroid {
             id: pid,
+            c: Collider::new(body.p, 0.0), // shape and radius come from set_size_class
             b: body,
-            c: collider,
-            size: 50.0,
+            size: size_class.size(),
             size_class,
-        }
+            fragment: false,
+            outline: outline(pid),
+        };
+        a.set_size_class(size_class);
+        a
     }
 
-    pub fn explode_asteroid(&mut self) -> Option<Vec<Asteroid>> {
-        // println!("HEREEEE");
-        match self.size_class {
-            AsteroidSizeClass::Small => {
-                // should be destroyed ...
-                self.b.state = BodyState::Dead;
-                self.c.off();
+    // SAT needs a convex shape, so the collider is the drawn outline with
+    // its dents filled in
+    pub fn set_size_class(&mut self, size_class: AsteroidSizeClass) {
+        self.size_class = size_class;
+        self.size = size_class.size();
+        let half = self.size / 2.0;
+        let scaled: Vec<[f64; 2]> = self
+            .outline
+            .iter()
+            .map(|v| [v[0] * half, v[1] * half])
+            .collect();
+        self.c.set_polygon(convex_hull(&scaled));
+    }
 
-                return None;
-            }
-            AsteroidSizeClass::Medium => {
-                // split into several small asteroids
-                // return (..ASTEROID_SPLIT_FACTOR).map(|i| {});
-                // return Some((..ASTEROID_SPLIT_FACTOR).map(|i| Asteroid::new(self.window_size)));
-                return Some(Vec::new());
-            }
-            AsteroidSizeClass::Large => {
-                // split into a combination of medium and small asteroids
-                // return (..ASTEROID_SPLIT_FACTOR).map(|i| {});
-                return Some(Vec::new());
+    // Destroy this asteroid and return the fragments it breaks into.
+    //
+    // A medium or large asteroid whose process has children breaks into
+    // one asteroid per child, so shooting a parent exposes its subtree.
+    // Without children it scatters into smaller pieces that share its pid.
+    // The process should only be killed once none of its asteroids are
+    // left alive, see `AsteroidManager::has_live`.
+    pub fn explode_asteroid<R: Rng>(
+        &mut self,
+        children: &[(Pid, AsteroidSizeClass)],
+        rng: &mut R,
+    ) -> Vec<Asteroid> {
+        self.b.state = BodyState::Dead;
+        self.c.off();
+
+        match self.size_class {
+            AsteroidSizeClass::Small => Vec::new(),
+            _ if !children.is_empty() => {
+                let n = children.len();
+                children
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (pid, class))| self.fragment(i, n, *class, *pid, rng))
+                    .collect()
             }
+            // split into several small asteroids
+            AsteroidSizeClass::Medium => (0..ASTEROID_SPLIT_FACTOR as usize)
+                .map(|i| self.scatter(i, AsteroidSizeClass::Small, rng))
+                .collect(),
+            // split into a combination of medium and small asteroids
+            AsteroidSizeClass::Large => (0..ASTEROID_SPLIT_FACTOR as usize)
+                .map(|i| match i % 2 {
+                    0 => self.scatter(i, AsteroidSizeClass::Medium, rng),
+                    _ => self.scatter(i, AsteroidSizeClass::Small, rng),
+                })
+                .collect(),
         }
+    }
+
+    fn scatter<R: Rng>(&self, i: usize, size_class: AsteroidSizeClass, rng: &mut R) -> Asteroid {
+        let n = ASTEROID_SPLIT_FACTOR as usize;
+        let mut a = self.fragment(i, n, size_class, self.id, rng);
+        a.fragment = true;
+        a
+    }
+
+    // the i-th of n pieces flying out of this asteroid
+    fn fragment<R: Rng>(
+        &self,
+        i: usize,
+        n: usize,
+        size_class: AsteroidSizeClass,
+        pid: Pid,
+        rng: &mut R,
+    ) -> Asteroid {
+        let angle = 2.0 * PI * i as f64 / n as f64 + rng.gen_range(-0.5..0.5);
+        let speed = ASTEROID_SPLIT_SPEED * rng.gen_range(1.0..ASTEROID_SPLIT_VARIANCE);
+
+        let mut mb = MovableBody::new(self.b.p, self.b.window_size);
+        mb.v = [
+            self.b.v[0] + speed * angle.cos(),
+            self.b.v[1] + speed * angle.sin(),
+        ];
+        mb.rv = rng.gen_range(-10.0..10.0);
+
+        Asteroid::new(mb, size_class, pid)
+    }
 
-        // remove asteroid from list if necessary
+    pub fn revive(&mut self) {
+        self.b.state = BodyState::Live;
+        self.c.on();
     }
 }
 
+// A jagged outline of radius 1 or a little less. It only depends on the pid,
+// so a process's asteroid looks the same every run and its fragments look
+// like it, and it leaves the game's rng alone. The corners go round the
+// centre in order, so every one of them can be seen from there.
+fn outline(pid: Pid) -> Vec<[f64; 2]> {
+    let mut rng = StdRng::seed_from_u64(pid as u64);
+    let n = rng.gen_range(OUTLINE_VERTICES);
+    (0..n)
+        .map(|i| {
+            let angle = 2.0 * PI * (i as f64 + rng.gen_range(-0.3..0.3)) / n as f64;
+            let r = 1.0 - rng.gen_range(0.0..OUTLINE_DENT);
+            [r * angle.cos(), r * angle.sin()]
+        })
+        .collect()
+}
+
 pub struct AsteroidManager {
     window_size: [f64; 2],
     pub asteroids: Vec<Asteroid>,
-    available_pids: Vec<Proc>,
+    pub sizing: SizeThresholds,
+    pub bounce: bool, // asteroids collide with each other
 }
 
 impl AsteroidManager {
-    pub fn new(window_size: [f64; 2]) -> AsteroidManager {
-        // TODO initially spawn several asteroids, but none should
-        // should be where the player spawns (center of screen)
-        let mut rng = rand::thread_rng();
+    pub fn new(window_size: [f64; 2], sizing: SizeThresholds) -> AsteroidManager {
+        // asteroids are spawned from the process table by `sync_procs`
         AsteroidManager {
             window_size,
-            asteroids: (1..10)
-                .map(|_| {
-                    let p: [f64; 2] = [
-                        rng.gen_range(0.0..window_size[0]),
-                        rng.gen_range(0.0..window_size[1]),
-                    ];
-                    let mut mb = MovableBody::new(p, window_size);
-                    mb.v = [rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)];
-                    mb.rv = rng.gen_range(-10.0..10.0);
-
-                    Asteroid::new(mb, Collider::new(p, 25.0), AsteroidSizeClass::Small)
-                })
-                .collect(),
+            asteroids: Vec::new(),
+            sizing,
+            bounce: false,
+        }
+    }
+
+    // Insert the pieces returned by `Asteroid::explode_asteroid`. A child
+    // process's fragment replaces the asteroid it already had, unless that
+    // one has been shot down or split itself.
+    pub fn add_fragments(&mut self, fragments: Vec<Asteroid>) {
+        for f in fragments {
+            if !f.fragment {
+                let taken = self
+                    .asteroids
+                    .iter()
+                    .any(|a| a.id == f.id && (a.fragment || a.b.state == BodyState::Dead));
+                if taken {
+                    continue;
+                }
+            }
 
-            available_pids: Vec::new(),
+            self.asteroids.retain(|a| a.id != f.id || a.fragment);
+            self.asteroids.push(f);
         }
     }
 
-    pub fn add_asteroid(&mut self, a: Asteroid) {
-        self.asteroids.push(a)
+    // no live asteroid comes within `r` of `p`
+    pub fn is_clear(&self, p: [f64; 2], r: f64) -> bool {
+        let area = Collider::new(p, r);
+        let grid = SpatialGrid::build(self.asteroids.iter().map(|a| &a.c));
+        grid.candidates(&area)
+            .into_iter()
+            .all(|i| !self.asteroids[i].c.are_colliding(&area))
     }
 
-    pub fn add_pid_asteroid(&mut self, pid: u32) {
-        let p: [f64; 2] = [
-            rng.gen_range(0.0..window_size[0]),
-            rng.gen_range(0.0..window_size[1]),
-        ];
+    pub fn has_live(&self, pid: Pid) -> bool {
+        self.asteroids
+            .iter()
+            .any(|a| a.id == pid && a.b.state == BodyState::Live)
+    }
+
+    pub fn add_proc_asteroid<R: Rng>(&mut self, proc: &Proc, rng: &mut R) {
+        let [w, h] = self.window_size;
+
+        // spawn on a random edge of the screen so new processes
+        // never appear on top of the player
+        let p: [f64; 2] = match rng.gen_range(0..4) {
+            0 => [rng.gen_range(0.0..w), 0.0],
+            1 => [rng.gen_range(0.0..w), h],
+            2 => [0.0, rng.gen_range(0.0..h)],
+            _ => [w, rng.gen_range(0.0..h)],
+        };
         let mut mb = MovableBody::new(p, self.window_size);
         mb.v = [rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)];
         mb.rv = rng.gen_range(-10.0..10.0);
 
         self.asteroids.push(Asteroid::new(
             mb,
-            Collider::new(p, 25.0),
-            AsteroidSizeClass::Small,
-            pid,
+            AsteroidSizeClass::for_proc(proc, &self.sizing),
+            proc.pid,
         ))
     }
 
+    // Mirror a process table snapshot: every pid gets exactly one asteroid,
+    // new pids spawn at the screen edge and asteroids whose pid has exited
+    // are removed. Asteroids that were shot down stay around (dead) until
+    // their process disappears so they don't respawn. Surviving asteroids
+    // are resized to the process's current usage.
+    pub fn sync_procs<'a, I, R>(&mut self, procs: I, rng: &mut R)
+    where
+        I: IntoIterator<Item = &'a Proc>,
+        R: Rng,
+    {
+        let live: BTreeMap<Pid, &Proc> = procs.into_iter().map(|p| (p.pid, p)).collect();
+        self.asteroids.retain(|a| live.contains_key(&a.id));
+
+        let sizing = self.sizing;
+        for asteroid in self.asteroids.iter_mut().filter(|a| !a.fragment) {
+            asteroid.set_size_class(AsteroidSizeClass::for_proc(live[&asteroid.id], &sizing));
+        }
+
+        let known: HashSet<Pid> = self.asteroids.iter().map(|a| a.id).collect();
+        for (pid, p) in live {
+            if !known.contains(&pid) {
+                self.add_proc_asteroid(p, rng);
+            }
+        }
+    }
+
+    // Follow the process table as it changes: new processes get an
+    // asteroid, exited ones lose all of theirs and changed ones are resized.
+    pub fn apply_events<R: Rng>(&mut self, events: &[ProcEvent], rng: &mut R) {
+        let sizing = self.sizing;
+        for event in events {
+            match event {
+                ProcEvent::Spawned(p) => {
+                    if !self.asteroids.iter().any(|a| a.id == p.pid) {
+                        self.add_proc_asteroid(p, rng);
+                    }
+                }
+                ProcEvent::Exited(pid) => self.asteroids.retain(|a| a.id != *pid),
+                ProcEvent::Changed(p) => {
+                    let size_class = AsteroidSizeClass::for_proc(p, &sizing);
+                    for asteroid in self
+                        .asteroids
+                        .iter_mut()
+                        .filter(|a| a.id == p.pid && !a.fragment)
+                    {
+                        asteroid.set_size_class(size_class);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn revive_pid(&mut self, pid: u32) {
+        for asteroid in self.asteroids.iter_mut().filter(|a| a.id == pid) {
+            asteroid.revive();
+        }
+    }
+
     pub fn update(&mut self, dt: f64) {
-        // check for asteroids that have gone out of bounds
-        // and give them a new position and trajectory
-        let n = self.asteroids.len();
-        let n_alive = self
-            .asteroids
-            .iter()
-            .filter(|a| a.b.state == BodyState::Live)
-            .count();
+        for asteroid in self.asteroids.iter_mut() {
+            asteroid.update(dt)
+        }
+    }
+}
 
-        // if n_alive is less than desired spawn more asteroids ...
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(1)
+    }
+
+    fn procs(pids: &[Pid]) -> Vec<Proc> {
+        pids.iter()
+            .map(|&pid| Proc::fake(pid, 1, "worker"))
+            .collect()
     }
 
-    pub fn get_available_pids(&mut self) {
-        self.available_pids = get_procs();
+    fn pids(m: &AsteroidManager) -> Vec<u32> {
+        let mut pids: Vec<u32> = m.asteroids.iter().map(|a| a.id).collect();
+        pids.sort();
+        pids
+    }
+
+    #[test]
+    fn one_asteroid_per_pid() {
+        let mut m = AsteroidManager::new([800.0, 800.0], SizeThresholds::default());
+        m.sync_procs(&procs(&[10, 11, 12]), &mut rng());
+        assert_eq!(pids(&m), vec![10, 11, 12]);
+
+        m.sync_procs(&procs(&[11, 12, 13]), &mut rng());
+        assert_eq!(pids(&m), vec![11, 12, 13]);
+
+        m.sync_procs(&[], &mut rng());
+        assert!(m.asteroids.is_empty());
+    }
+
+    #[test]
+    fn new_asteroids_start_at_the_edge() {
+        let mut m = AsteroidManager::new([800.0, 600.0], SizeThresholds::default());
+        m.sync_procs(&procs(&(0..50).collect::<Vec<Pid>>()), &mut rng());
+        for a in &m.asteroids {
+            let [x, y] = a.b.p;
+            assert!(x == 0.0 || x == 800.0 || y == 0.0 || y == 600.0);
+        }
+    }
+
+    #[test]
+    fn spawns_follow_the_seed() {
+        let spawn = |seed| {
+            let mut m = AsteroidManager::new([800.0, 600.0], SizeThresholds::default());
+            m.sync_procs(&procs(&[10, 11, 12]), &mut StdRng::seed_from_u64(seed));
+            m.asteroids
+                .iter()
+                .map(|a| (a.b.p, a.b.v))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spawn(7), spawn(7));
+        assert_ne!(spawn(7), spawn(8));
+    }
+
+    #[test]
+    fn shot_asteroids_stay_down_while_the_pid_lives() {
+        let mut m = AsteroidManager::new([800.0, 800.0], SizeThresholds::default());
+        m.sync_procs(&procs(&[10, 11]), &mut rng());
+        m.asteroids[0].b.state = BodyState::Dead;
+        let dead = m.asteroids[0].id;
+
+        m.sync_procs(&procs(&[10, 11]), &mut rng());
+        assert_eq!(m.asteroids.len(), 2);
+        let shot = m.asteroids.iter().find(|a| a.id == dead).unwrap();
+        assert!(shot.b.state == BodyState::Dead);
+    }
+
+    const MB: u64 = 1024 * 1024;
+
+    fn using(rss: u64, cpu_secs: u64) -> Proc {
+        let mut p = Proc::fake(10, 1, "worker");
+        p.rss = rss;
+        p.cpu_time = std::time::Duration::from_secs(cpu_secs);
+        p
+    }
+
+    #[test]
+    fn size_class_follows_the_thresholds() {
+        let memory = SizeThresholds::new(SizeMetric::Memory);
+        let cpu = SizeThresholds::new(SizeMetric::CpuTime);
+        let cases = [
+            (
+                using(0, 0),
+                AsteroidSizeClass::Small,
+                AsteroidSizeClass::Small,
+            ),
+            (
+                using(99 * MB, 59),
+                AsteroidSizeClass::Small,
+                AsteroidSizeClass::Small,
+            ),
+            (
+                using(100 * MB, 60),
+                AsteroidSizeClass::Medium,
+                AsteroidSizeClass::Medium,
+            ),
+            (
+                using(500 * MB, 0),
+                AsteroidSizeClass::Large,
+                AsteroidSizeClass::Small,
+            ),
+            (
+                using(0, 600),
+                AsteroidSizeClass::Small,
+                AsteroidSizeClass::Large,
+            ),
+        ];
+
+        for (proc, by_memory, by_cpu) in cases.iter() {
+            assert_eq!(AsteroidSizeClass::for_proc(proc, &memory), *by_memory);
+            assert_eq!(AsteroidSizeClass::for_proc(proc, &cpu), *by_cpu);
+        }
+    }
+
+    #[test]
+    fn sync_resizes_as_usage_changes() {
+        let mut m = AsteroidManager::new([800.0, 800.0], SizeThresholds::default());
+        m.sync_procs(&[using(0, 0)], &mut rng());
+        assert_eq!(m.asteroids[0].size_class, AsteroidSizeClass::Small);
+        let small = m.asteroids[0].c.r;
+
+        m.sync_procs(&[using(600 * MB, 0)], &mut rng());
+        assert_eq!(m.asteroids.len(), 1);
+        assert_eq!(m.asteroids[0].size_class, AsteroidSizeClass::Large);
+        assert!(m.asteroids[0].c.r > small);
+    }
+
+    const PARENT: Pid = 10;
+
+    // asteroids lined up along y = 400, the first at x = 100
+    fn field(asteroids: &[(Pid, AsteroidSizeClass)]) -> AsteroidManager {
+        let window = [800.0, 800.0];
+        let mut m = AsteroidManager::new(window, SizeThresholds::default());
+        for (i, (pid, class)) in asteroids.iter().enumerate() {
+            let body = MovableBody::new([100.0 * (i + 1) as f64, 400.0], window);
+            m.asteroids.push(Asteroid::new(body, *class, *pid));
+        }
+        m
+    }
+
+    // shoot the first asteroid with `pid` and put its pieces in the field
+    fn shoot(m: &mut AsteroidManager, pid: Pid, children: &[(Pid, AsteroidSizeClass)]) {
+        let i = m.asteroids.iter().position(|a| a.id == pid).unwrap();
+        let fragments = m.asteroids[i].explode_asteroid(children, &mut rng());
+        m.add_fragments(fragments);
+    }
+
+    fn with_pid(m: &AsteroidManager, pid: Pid) -> Vec<&Asteroid> {
+        m.asteroids.iter().filter(|a| a.id == pid).collect()
+    }
+
+    #[test]
+    fn children_replace_their_asteroids() {
+        let mut m = field(&[
+            (PARENT, AsteroidSizeClass::Large),
+            (11, AsteroidSizeClass::Small),
+            (12, AsteroidSizeClass::Small),
+        ]);
+        shoot(
+            &mut m,
+            PARENT,
+            &[
+                (11, AsteroidSizeClass::Medium),
+                (12, AsteroidSizeClass::Small),
+            ],
+        );
+
+        for (pid, class) in &[
+            (11, AsteroidSizeClass::Medium),
+            (12, AsteroidSizeClass::Small),
+        ] {
+            let children = with_pid(&m, *pid);
+            assert_eq!(children.len(), 1);
+            assert!(children[0].b.state == BodyState::Live);
+            assert!(!children[0].fragment);
+            assert_eq!(children[0].size, class.size());
+            // flung out from where the parent was
+            assert_eq!(children[0].b.p, [100.0, 400.0]);
+        }
+        assert_eq!(with_pid(&m, PARENT).len(), 1);
+        assert!(!m.has_live(PARENT));
+    }
+
+    #[test]
+    fn childless_asteroids_scatter() {
+        for (class, pieces) in &[
+            (AsteroidSizeClass::Medium, [AsteroidSizeClass::Small; 4]),
+            (
+                AsteroidSizeClass::Large,
+                [
+                    AsteroidSizeClass::Medium,
+                    AsteroidSizeClass::Small,
+                    AsteroidSizeClass::Medium,
+                    AsteroidSizeClass::Small,
+                ],
+            ),
+        ] {
+            let mut m = field(&[(PARENT, *class)]);
+            shoot(&mut m, PARENT, &[]);
+
+            // the shot asteroid is gone, only its pieces are left
+            let fragments = with_pid(&m, PARENT);
+            assert_eq!(fragments.len(), ASTEROID_SPLIT_FACTOR as usize);
+            for (f, piece) in fragments.iter().zip(pieces.iter()) {
+                assert!(f.fragment);
+                assert!(f.b.state == BodyState::Live);
+                assert_eq!(f.size, piece.size());
+            }
+        }
+    }
+
+    #[test]
+    fn small_asteroids_just_die() {
+        let mut m = field(&[(PARENT, AsteroidSizeClass::Small)]);
+        shoot(&mut m, PARENT, &[(11, AsteroidSizeClass::Small)]);
+        assert_eq!(m.asteroids.len(), 1);
+        assert!(!m.has_live(PARENT));
+        assert!(!m.has_live(11));
+    }
+
+    #[test]
+    fn dead_children_stay_dead() {
+        let mut m = field(&[
+            (PARENT, AsteroidSizeClass::Large),
+            (11, AsteroidSizeClass::Small),
+        ]);
+        shoot(&mut m, 11, &[]);
+        shoot(&mut m, PARENT, &[(11, AsteroidSizeClass::Small)]);
+
+        let child = with_pid(&m, 11);
+        assert_eq!(child.len(), 1);
+        assert!(child[0].b.state == BodyState::Dead);
+        assert_eq!(child[0].b.p, [200.0, 400.0]);
+    }
+
+    #[test]
+    fn split_children_are_not_merged_back() {
+        let mut m = field(&[
+            (PARENT, AsteroidSizeClass::Large),
+            (11, AsteroidSizeClass::Medium),
+        ]);
+        shoot(&mut m, 11, &[]);
+        shoot(&mut m, PARENT, &[(11, AsteroidSizeClass::Small)]);
+
+        let pieces = with_pid(&m, 11);
+        assert_eq!(pieces.len(), ASTEROID_SPLIT_FACTOR as usize);
+        assert!(pieces.iter().all(|a| a.fragment));
+    }
+
+    #[test]
+    fn live_until_the_last_fragment_dies() {
+        let mut m = field(&[(PARENT, AsteroidSizeClass::Medium)]);
+        assert!(m.has_live(PARENT));
+        shoot(&mut m, PARENT, &[]);
+
+        for left in (0..ASTEROID_SPLIT_FACTOR).rev() {
+            assert!(m.has_live(PARENT));
+            let i = m
+                .asteroids
+                .iter()
+                .position(|a| a.b.state == BodyState::Live)
+                .unwrap();
+            assert!(m.asteroids[i].explode_asteroid(&[], &mut rng()).is_empty());
+            assert_eq!(
+                m.asteroids
+                    .iter()
+                    .filter(|a| a.b.state == BodyState::Live)
+                    .count(),
+                left as usize
+            );
+        }
+        assert!(!m.has_live(PARENT));
     }
 }
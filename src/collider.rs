@@ -1,6 +1,7 @@
-use std::cmp;
+use vecmath::{vec2_add, vec2_dot, vec2_len, vec2_scale, vec2_sub, Vector2};
 
-use vecmath::Vector2;
+// cells are never smaller than this, however small the colliders
+const MIN_CELL_SIZE: f64 = 16.0;
 
 #[derive(Copy, Clone)]
 enum ColliderState {
@@ -8,11 +9,32 @@ enum ColliderState {
     Disabled,
 }
 
-#[derive(Copy, Clone)]
+// Where two colliders touch. `normal` is a unit vector pointing from the
+// first collider towards the second, `point` is halfway through the overlap
+// and `penetration` is how far they would have to move apart to just touch.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Contact {
+    pub point: Vector2<f64>,
+    pub normal: Vector2<f64>,
+    pub penetration: f64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Shape {
+    Circle,
+    // convex, vertices in order around `p` before rotating by `angle`
+    Polygon(Vec<Vector2<f64>>),
+}
+
+// `r` is the radius for circles and the bounding radius for polygons, so
+// the broad phase can treat everything as a circle.
+#[derive(Clone)]
 pub struct Collider {
     state: ColliderState,
     pub p: Vector2<f64>,
     pub r: f64,
+    pub angle: f64, // radians, same sense as graphics' rot_rad
+    pub shape: Shape,
 }
 
 impl Collider {
@@ -21,26 +43,176 @@ impl Collider {
             state: ColliderState::Enabled,
             p,
             r,
+            angle: 0.0,
+            shape: Shape::Circle,
+        }
+    }
+
+    pub fn polygon(p: Vector2<f64>, vertices: Vec<Vector2<f64>>) -> Collider {
+        let mut c = Collider::new(p, 0.0);
+        c.set_polygon(vertices);
+        c
+    }
+
+    pub fn set_polygon(&mut self, vertices: Vec<Vector2<f64>>) {
+        self.r = vertices.iter().fold(0.0, |r: f64, v| r.max(vec2_len(*v)));
+        self.shape = Shape::Polygon(vertices);
+    }
+
+    // the polygon's vertices in world space
+    pub fn vertices(&self) -> Vec<Vector2<f64>> {
+        match &self.shape {
+            Shape::Circle => Vec::new(),
+            Shape::Polygon(vertices) => {
+                let (sin, cos) = self.angle.sin_cos();
+                vertices
+                    .iter()
+                    .map(|v| {
+                        [
+                            self.p[0] + v[0] * cos - v[1] * sin,
+                            self.p[1] + v[0] * sin + v[1] * cos,
+                        ]
+                    })
+                    .collect()
+            }
         }
     }
 
-    // TODO also return points of collision
     pub fn are_colliding(&self, other: &Collider) -> bool {
-        match (self.state, other.state) {
-            (ColliderState::Disabled, _) => false,
-            (_, ColliderState::Disabled) => false,
-            (_, _) => {
-                let [x1, y1] = self.p;
-                let [x2, y2] = other.p;
+        self.contact(other).is_some()
+    }
+
+    // Touching counts as colliding.
+    pub fn contact(&self, other: &Collider) -> Option<Contact> {
+        if !self.is_enabled() || !other.is_enabled() {
+            return None;
+        }
+
+        // nothing can touch if the bounding circles don't
+        let d = vec2_sub(other.p, self.p);
+        let reach = self.r + other.r;
+        if d[0] * d[0] + d[1] * d[1] > reach * reach {
+            return None;
+        }
 
-                // println!(
-                //     "{} : {}",
-                //     (x2 - x1).powf(2.0) + (y2 - y1).powf(2.0),
-                //     self.r.max(other.r).powf(2.0)
-                // );
-                (x2 - x1).powf(2.0) + (y2 - y1).powf(2.0) <= self.r.max(other.r).powf(2.0)
+        match (&self.shape, &other.shape) {
+            (Shape::Circle, Shape::Circle) => self.circle_contact(other),
+            _ => self.sat_contact(other),
+        }
+    }
+
+    // Circles overlap when their centres are no further apart than the sum
+    // of their radii.
+    fn circle_contact(&self, other: &Collider) -> Option<Contact> {
+        let d = vec2_sub(other.p, self.p);
+        let reach = self.r + other.r;
+        let dist_sq = d[0] * d[0] + d[1] * d[1];
+        if dist_sq > reach * reach {
+            return None;
+        }
+
+        let dist = vec2_len(d);
+        // same centre, any direction will do
+        let normal = if dist > 0.0 {
+            vec2_scale(d, 1.0 / dist)
+        } else {
+            [1.0, 0.0]
+        };
+        let penetration = reach - dist;
+
+        Some(Contact {
+            point: vec2_add(self.p, vec2_scale(normal, self.r - penetration / 2.0)),
+            normal,
+            penetration,
+        })
+    }
+
+    // Separating axis test for anything involving a polygon. Two convex
+    // shapes are apart if and only if there is an axis their projections
+    // don't overlap on, and the only candidates are the polygons' edge
+    // normals plus, for a circle, the line from its centre to the nearest
+    // vertex. The axis with the least overlap gives the contact normal.
+    fn sat_contact(&self, other: &Collider) -> Option<Contact> {
+        let (mine, theirs) = (self.vertices(), other.vertices());
+        let mut axes = edge_normals(&mine);
+        axes.extend(edge_normals(&theirs));
+        if let Some(axis) = self.circle_axis(&theirs) {
+            axes.push(axis);
+        }
+        if let Some(axis) = other.circle_axis(&mine) {
+            axes.push(axis);
+        }
+
+        let mut best: Option<(f64, Vector2<f64>)> = None;
+        for axis in axes {
+            let (a_min, a_max) = self.project(&mine, axis);
+            let (b_min, b_max) = other.project(&theirs, axis);
+            let overlap = (a_max - b_min).min(b_max - a_min);
+            if overlap < 0.0 {
+                return None;
             }
+            if best.is_none_or(|(least, _)| overlap < least) {
+                best = Some((overlap, axis));
+            }
+        }
+
+        let (penetration, mut normal) = best?;
+        if vec2_dot(vec2_sub(other.p, self.p), normal) < 0.0 {
+            normal = vec2_scale(normal, -1.0);
+        }
+
+        // halfway through the overlap from the deepest point of `other`
+        let deepest = other.support(&theirs, vec2_scale(normal, -1.0));
+        Some(Contact {
+            point: vec2_add(deepest, vec2_scale(normal, penetration / 2.0)),
+            normal,
+            penetration,
+        })
+    }
+
+    // for a circle, the axis towards the closest of the other shape's vertices
+    fn circle_axis(&self, vertices: &[Vector2<f64>]) -> Option<Vector2<f64>> {
+        if self.shape != Shape::Circle {
+            return None;
+        }
+        let closest = vertices.iter().min_by(|a, b| {
+            let (da, db) = (vec2_sub(**a, self.p), vec2_sub(**b, self.p));
+            vec2_dot(da, da).total_cmp(&vec2_dot(db, db))
+        })?;
+        let d = vec2_sub(*closest, self.p);
+        let len = vec2_len(d);
+        if len > 0.0 {
+            Some(vec2_scale(d, 1.0 / len))
+        } else {
+            None
+        }
+    }
+
+    // the extent of the shape along a unit axis
+    fn project(&self, vertices: &[Vector2<f64>], axis: Vector2<f64>) -> (f64, f64) {
+        if self.shape == Shape::Circle {
+            let centre = vec2_dot(self.p, axis);
+            return (centre - self.r, centre + self.r);
         }
+        vertices.iter().fold((f64::MAX, f64::MIN), |(lo, hi), v| {
+            let d = vec2_dot(*v, axis);
+            (lo.min(d), hi.max(d))
+        })
+    }
+
+    // the point of the shape furthest along `dir`
+    fn support(&self, vertices: &[Vector2<f64>], dir: Vector2<f64>) -> Vector2<f64> {
+        if self.shape == Shape::Circle {
+            return vec2_add(self.p, vec2_scale(dir, self.r));
+        }
+        *vertices
+            .iter()
+            .max_by(|a, b| vec2_dot(**a, dir).total_cmp(&vec2_dot(**b, dir)))
+            .unwrap_or(&self.p)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.state, ColliderState::Enabled)
     }
 
     pub fn on(&mut self) {
@@ -51,3 +223,439 @@ impl Collider {
         self.state = ColliderState::Disabled;
     }
 }
+
+// unit normals of each edge of a polygon given in world space
+fn edge_normals(vertices: &[Vector2<f64>]) -> Vec<Vector2<f64>> {
+    (0..vertices.len())
+        .filter_map(|i| {
+            let edge = vec2_sub(vertices[(i + 1) % vertices.len()], vertices[i]);
+            let len = vec2_len(edge);
+            if len > 0.0 {
+                Some([-edge[1] / len, edge[0] / len])
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// The smallest convex polygon around `points`, for colliding with outlines
+// that aren't convex themselves. Vertices come out in order, starting from
+// the lowest x.
+pub fn convex_hull(points: &[Vector2<f64>]) -> Vec<Vector2<f64>> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut hull = half_hull(sorted.iter());
+    hull.extend(half_hull(sorted.iter().rev()));
+    hull
+}
+
+// One side of the hull, walking `points` in order and dropping any that
+// would make a clockwise turn (in y-up coordinates). The last point starts
+// the other side, so it is left off.
+fn half_hull<'a, I>(points: I) -> Vec<Vector2<f64>>
+where
+    I: Iterator<Item = &'a Vector2<f64>>,
+{
+    let cross = |o: Vector2<f64>, a: Vector2<f64>, b: Vector2<f64>| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+
+    let mut half: Vec<Vector2<f64>> = Vec::new();
+    for &p in points {
+        while half.len() >= 2 && cross(half[half.len() - 2], half[half.len() - 1], p) <= 0.0 {
+            half.pop();
+        }
+        half.push(p);
+    }
+    half.pop();
+    half
+}
+
+// Broad phase: a uniform grid over the colliders' bounding box where each
+// cell lists the colliders overlapping it, so a query only has to look at
+// its neighbours instead of every collider in the field. The cells are packed
+// into one vec and rebuilt from scratch every step.
+pub struct SpatialGrid {
+    cell: f64,
+    origin: Vector2<f64>,
+    cols: usize,
+    rows: usize,
+    starts: Vec<usize>,  // cell i holds entries[starts[i]..starts[i + 1]]
+    entries: Vec<usize>, // collider indices
+}
+
+impl SpatialGrid {
+    // A grid over `colliders`, indexed by their position in the iterator.
+    // Cells are as wide as the biggest collider so each one only lands in a
+    // handful of them. Disabled colliders are left out.
+    pub fn build<'a, I>(colliders: I) -> SpatialGrid
+    where
+        I: IntoIterator<Item = &'a Collider>,
+    {
+        let enabled: Vec<(usize, &Collider)> = colliders
+            .into_iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_enabled())
+            .collect();
+
+        let mut min = [f64::MAX, f64::MAX];
+        let mut max = [f64::MIN, f64::MIN];
+        let mut largest: f64 = 0.0;
+        for (_, c) in enabled.iter() {
+            min = [min[0].min(c.p[0] - c.r), min[1].min(c.p[1] - c.r)];
+            max = [max[0].max(c.p[0] + c.r), max[1].max(c.p[1] + c.r)];
+            largest = largest.max(c.r);
+        }
+        if enabled.is_empty() {
+            min = [0.0, 0.0];
+            max = [0.0, 0.0];
+        }
+
+        // don't let a few far apart colliders make a huge sparse grid
+        let mut cell = (largest * 2.0).max(MIN_CELL_SIZE);
+        let max_cells = (enabled.len() * 4).max(16);
+        let dims = |cell: f64| {
+            let cols = ((max[0] - min[0]) / cell).floor() as usize + 1;
+            let rows = ((max[1] - min[1]) / cell).floor() as usize + 1;
+            (cols, rows)
+        };
+        while dims(cell).0 * dims(cell).1 > max_cells {
+            cell *= 2.0;
+        }
+        let (cols, rows) = dims(cell);
+
+        let mut grid = SpatialGrid {
+            cell,
+            origin: min,
+            cols,
+            rows,
+            starts: vec![0; cols * rows + 1],
+            entries: Vec::new(),
+        };
+
+        // count what goes in each cell, then fill them in place
+        for (_, c) in enabled.iter() {
+            for key in grid.cells_under(c) {
+                grid.starts[key + 1] += 1;
+            }
+        }
+        for i in 1..grid.starts.len() {
+            grid.starts[i] += grid.starts[i - 1];
+        }
+        let mut next = grid.starts.clone();
+        grid.entries = vec![0; grid.starts[cols * rows]];
+        for (index, c) in enabled.iter() {
+            for key in grid.cells_under(c) {
+                grid.entries[next[key]] = *index;
+                next[key] += 1;
+            }
+        }
+
+        grid
+    }
+
+    // Indices of everything sharing a cell with `c`, in ascending order so
+    // callers see the same pairs in the same order every run. These still
+    // need a narrow phase check.
+    pub fn candidates(&self, c: &Collider) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .cells_under(c)
+            .flat_map(|key| &self.entries[self.starts[key]..self.starts[key + 1]])
+            .cloned()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    // the cells `c`'s bounding box covers, clipped to the grid
+    fn cells_under(&self, c: &Collider) -> impl Iterator<Item = usize> {
+        let (cell, origin, cols, rows) = (self.cell, self.origin, self.cols, self.rows);
+        let range = move |lo: f64, hi: f64, o: f64, n: usize| {
+            let lo = ((lo - o) / cell).floor().max(0.0) as usize;
+            let hi = ((hi - o) / cell).floor();
+            if hi < 0.0 {
+                return 0..0;
+            }
+            lo..(hi as usize + 1).min(n)
+        };
+        let xs = range(c.p[0] - c.r, c.p[0] + c.r, origin[0], cols);
+        let ys = range(c.p[1] - c.r, c.p[1] + c.r, origin[1], rows);
+        ys.flat_map(move |y| xs.clone().map(move |x| y * cols + x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn apart_circles_do_not_collide() {
+        let a = Collider::new([0.0, 0.0], 10.0);
+        let b = Collider::new([25.0, 0.0], 10.0);
+        assert!(a.contact(&b).is_none());
+        assert!(!a.are_colliding(&b));
+    }
+
+    #[test]
+    fn small_circle_on_the_edge_of_a_big_one_collides() {
+        // the old max(r1, r2) test missed this
+        let asteroid = Collider::new([0.0, 0.0], 40.0);
+        let projectile = Collider::new([41.0, 0.0], 2.0);
+        assert!(projectile.are_colliding(&asteroid));
+        assert!(asteroid.are_colliding(&projectile));
+    }
+
+    #[test]
+    fn touching_counts() {
+        let a = Collider::new([0.0, 0.0], 10.0);
+        let b = Collider::new([0.0, 15.0], 5.0);
+        let contact = a.contact(&b).unwrap();
+        assert!(close(contact.penetration, 0.0));
+        assert_eq!(contact.point, [0.0, 10.0]);
+    }
+
+    #[test]
+    fn contact_points_from_first_to_second() {
+        let a = Collider::new([0.0, 0.0], 10.0);
+        let b = Collider::new([12.0, 0.0], 4.0);
+        let contact = a.contact(&b).unwrap();
+        assert_eq!(contact.normal, [1.0, 0.0]);
+        assert!(close(contact.penetration, 2.0));
+        assert!(close(contact.point[0], 9.0));
+
+        let back = b.contact(&a).unwrap();
+        assert_eq!(back.normal, [-1.0, 0.0]);
+        assert!(close(back.penetration, 2.0));
+        assert!(close(back.point[0], 9.0));
+    }
+
+    #[test]
+    fn normal_is_unit_length() {
+        let a = Collider::new([1.0, 2.0], 5.0);
+        let b = Collider::new([4.0, 6.0], 5.0);
+        let contact = a.contact(&b).unwrap();
+        assert!(close(vec2_len(contact.normal), 1.0));
+        assert!(close(contact.penetration, 5.0));
+    }
+
+    #[test]
+    fn same_centre_still_gives_a_normal() {
+        let a = Collider::new([3.0, 3.0], 5.0);
+        let b = Collider::new([3.0, 3.0], 2.0);
+        let contact = a.contact(&b).unwrap();
+        assert!(close(vec2_len(contact.normal), 1.0));
+        assert!(close(contact.penetration, 7.0));
+    }
+
+    fn square(p: Vector2<f64>, half: f64) -> Collider {
+        Collider::polygon(
+            p,
+            vec![[-half, -half], [half, -half], [half, half], [-half, half]],
+        )
+    }
+
+    #[test]
+    fn circle_misses_square_corner_inside_bounding_circle() {
+        // within the square's bounding circle but clear of the corner
+        let sq = square([0.0, 0.0], 10.0);
+        let c = Collider::new([13.0, 8.0], 2.0);
+        assert!(sq.r + c.r > vec2_len(c.p));
+        assert!(sq.contact(&c).is_none());
+        assert!(c.contact(&sq).is_none());
+    }
+
+    #[test]
+    fn circle_hits_square_face() {
+        let sq = square([0.0, 0.0], 10.0);
+        let c = Collider::new([11.0, 0.0], 2.0);
+        let contact = sq.contact(&c).unwrap();
+        assert!(close(contact.normal[0], 1.0));
+        assert!(close(contact.normal[1], 0.0));
+        assert!(close(contact.penetration, 1.0));
+        assert!(close(contact.point[0], 9.5));
+
+        let back = c.contact(&sq).unwrap();
+        assert!(close(back.normal[0], -1.0));
+        assert!(close(back.penetration, 1.0));
+    }
+
+    #[test]
+    fn rotating_a_square_changes_what_it_hits() {
+        // a diamond reaches further along the x axis than a square
+        let mut sq = square([0.0, 0.0], 10.0);
+        let c = Collider::new([13.0, 0.0], 2.0);
+        assert!(sq.contact(&c).is_none());
+        sq.angle = std::f64::consts::FRAC_PI_4;
+        assert!(sq.contact(&c).is_some());
+    }
+
+    #[test]
+    fn squares_overlap_along_the_shallowest_axis() {
+        let a = square([0.0, 0.0], 10.0);
+        let b = square([18.0, 5.0], 10.0);
+        let contact = a.contact(&b).unwrap();
+        assert!(close(contact.normal[0], 1.0));
+        assert!(close(contact.normal[1], 0.0));
+        assert!(close(contact.penetration, 2.0));
+    }
+
+    #[test]
+    fn squares_apart_on_a_diagonal() {
+        // bounding circles overlap but the squares don't
+        let mut a = square([0.0, 0.0], 10.0);
+        let b = square([23.0, 0.0], 10.0);
+        assert!(a.contact(&b).is_none());
+        a.angle = std::f64::consts::FRAC_PI_4;
+        assert!(a.contact(&b).is_some());
+    }
+
+    #[test]
+    fn triangle_against_square() {
+        let tri = Collider::polygon([0.0, 0.0], vec![[-10.0, -10.0], [10.0, -10.0], [0.0, 10.0]]);
+        let below = square([0.0, -19.5], 10.0);
+        let beside = square([21.0, 5.0], 10.0);
+        assert!(tri.contact(&below).is_some());
+        assert!(tri.contact(&beside).is_none());
+    }
+
+    #[test]
+    fn disabled_colliders_never_collide() {
+        let mut a = Collider::new([0.0, 0.0], 10.0);
+        let b = Collider::new([1.0, 0.0], 10.0);
+        a.off();
+        assert!(a.contact(&b).is_none());
+        assert!(b.contact(&a).is_none());
+        a.on();
+        assert!(a.contact(&b).is_some());
+    }
+
+    #[test]
+    fn hull_drops_the_dents() {
+        let star = [
+            [10.0, 0.0],
+            [3.0, 3.0],
+            [0.0, 10.0],
+            [-3.0, 3.0],
+            [-10.0, 0.0],
+            [-3.0, -3.0],
+            [0.0, -10.0],
+            [3.0, -3.0],
+        ];
+        let hull = convex_hull(&star);
+        assert_eq!(
+            hull,
+            vec![[-10.0, 0.0], [0.0, -10.0], [10.0, 0.0], [0.0, 10.0]]
+        );
+    }
+
+    // every pair that really collides, by checking them all
+    fn brute_force(colliders: &[Collider], c: &Collider) -> Vec<usize> {
+        (0..colliders.len())
+            .filter(|&i| colliders[i].are_colliding(c))
+            .collect()
+    }
+
+    #[test]
+    fn grid_finds_everything_brute_force_does() {
+        // a lattice whose spacing doesn't line up with the cells, so plenty
+        // of colliders straddle cell borders
+        let colliders: Vec<Collider> = (0..200)
+            .map(|i| {
+                let p = [(i % 20) as f64 * 13.7, (i / 20) as f64 * 11.3];
+                Collider::new(p, 4.0 + (i % 7) as f64)
+            })
+            .collect();
+        let grid = SpatialGrid::build(colliders.iter());
+
+        for c in colliders.iter() {
+            let found = grid.candidates(c);
+            let mut sorted = found.clone();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(found, sorted);
+            for i in brute_force(&colliders, c) {
+                assert!(found.contains(&i));
+            }
+        }
+    }
+
+    #[test]
+    fn straddling_collider_is_in_every_cell_it_touches() {
+        let colliders = [
+            Collider::new([0.0, 0.0], 10.0),
+            Collider::new([40.0, 40.0], 10.0),
+            Collider::new([30.0, 30.0], 10.0),
+        ];
+        let grid = SpatialGrid::build(colliders.iter());
+        assert_eq!(grid.cell, 20.0);
+
+        // the middle one sits on the corner of four cells
+        assert_eq!(grid.cells_under(&colliders[2]).count(), 4);
+        for probe in [[22.0, 22.0], [38.0, 22.0], [22.0, 38.0], [38.0, 38.0]] {
+            let found = grid.candidates(&Collider::new(probe, 1.0));
+            assert!(found.contains(&2), "missed from {:?}", probe);
+        }
+    }
+
+    #[test]
+    fn queries_outside_the_grid() {
+        let colliders = [
+            Collider::new([0.0, 0.0], 10.0),
+            Collider::new([100.0, 0.0], 10.0),
+        ];
+        let grid = SpatialGrid::build(colliders.iter());
+
+        assert!(grid
+            .candidates(&Collider::new([-500.0, -500.0], 10.0))
+            .is_empty());
+        assert!(grid
+            .candidates(&Collider::new([500.0, 500.0], 10.0))
+            .is_empty());
+        // hanging over the edge still finds what's inside
+        assert_eq!(grid.candidates(&Collider::new([-15.0, 0.0], 10.0)), vec![0]);
+        assert_eq!(grid.candidates(&Collider::new([115.0, 0.0], 10.0)), vec![1]);
+    }
+
+    #[test]
+    fn disabled_colliders_are_left_out() {
+        let mut colliders = [
+            Collider::new([0.0, 0.0], 10.0),
+            Collider::new([5.0, 0.0], 10.0),
+        ];
+        colliders[1].off();
+        let grid = SpatialGrid::build(colliders.iter());
+
+        assert_eq!(grid.candidates(&Collider::new([5.0, 0.0], 10.0)), vec![0]);
+    }
+
+    #[test]
+    fn far_apart_colliders_get_bigger_cells() {
+        let colliders = [
+            Collider::new([0.0, 0.0], 1.0),
+            Collider::new([100_000.0, 100_000.0], 1.0),
+        ];
+        let grid = SpatialGrid::build(colliders.iter());
+
+        assert!(grid.cell > MIN_CELL_SIZE);
+        assert!(grid.cols * grid.rows <= 16);
+        assert_eq!(grid.candidates(&colliders[0]), vec![0]);
+        assert_eq!(grid.candidates(&colliders[1]), vec![1]);
+    }
+
+    #[test]
+    fn empty_grid_finds_nothing() {
+        let grid = SpatialGrid::build(Vec::<Collider>::new().iter());
+        assert!(grid.candidates(&Collider::new([0.0, 0.0], 10.0)).is_empty());
+    }
+}
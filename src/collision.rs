@@ -0,0 +1,431 @@
+use asteroid::{Asteroid, AsteroidSizeClass};
+use collider::{Contact, SpatialGrid};
+use movable_body::BodyState;
+use movable_body::MovableBody;
+use proc::Pid;
+use simulation::{GameState, Phase};
+use traits::*;
+
+// One side of a collision. `index` points into the manager's vec for
+// projectiles and asteroids and is always 0 for the player.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct EntityRef {
+    pub kind: ColliderType,
+    pub index: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionEvent {
+    pub a: EntityRef,
+    pub b: EntityRef,
+    pub contact: Contact, // normal points from a to b
+}
+
+impl CollisionEvent {
+    fn new<A: Collides, B: Collides>(
+        a: &A,
+        ai: usize,
+        b: &B,
+        bi: usize,
+        contact: Contact,
+    ) -> CollisionEvent {
+        CollisionEvent {
+            a: EntityRef {
+                kind: a.collider_type(),
+                index: ai,
+            },
+            b: EntityRef {
+                kind: b.collider_type(),
+                index: bi,
+            },
+            contact,
+        }
+    }
+}
+
+// Collision phase: record every overlapping pair without touching any state.
+// The asteroids go into a grid first so each player or projectile is only
+// tested against the asteroids near it.
+pub fn detect_collisions(game: &GameState) -> Vec<CollisionEvent> {
+    let asteroids = &game.asteroid_manager.asteroids;
+    let grid = SpatialGrid::build(asteroids.iter().map(|a| a.get_collider()));
+    let mut events = Vec::new();
+
+    for ai in grid.candidates(game.player.get_collider()) {
+        if let Some(contact) = game.player.contact_with(&asteroids[ai]) {
+            events.push(CollisionEvent::new(
+                &game.player,
+                0,
+                &asteroids[ai],
+                ai,
+                contact,
+            ));
+        }
+    }
+
+    for (pi, projectile) in game.projectile_manager.projectiles.iter().enumerate() {
+        for ai in grid.candidates(projectile.get_collider()) {
+            if let Some(contact) = projectile.contact_with(&asteroids[ai]) {
+                events.push(CollisionEvent::new(
+                    projectile,
+                    pi,
+                    &asteroids[ai],
+                    ai,
+                    contact,
+                ));
+            }
+        }
+    }
+
+    events
+}
+
+// Resolution phase: apply the game rules for each recorded collision. Events
+// whose entities were already destroyed earlier in the same pass are skipped,
+// so one projectile never takes out two asteroids. Shooting a process on the
+// confirm list stops the game on a prompt instead, see `confirm_kill`.
+pub fn resolve_collisions(game: &mut GameState, events: Vec<CollisionEvent>) {
+    let mut fragments = Vec::new();
+    let mut hit: Vec<Pid> = Vec::new();
+
+    for event in events {
+        match (event.a.kind, event.b.kind) {
+            (ColliderType::Projectile, ColliderType::Asteroid) => {
+                let projectile = &mut game.projectile_manager.projectiles[event.a.index];
+                let asteroid = &mut game.asteroid_manager.asteroids[event.b.index];
+                if projectile.b.state == BodyState::Dead || asteroid.b.state == BodyState::Dead {
+                    continue;
+                }
+
+                projectile.on_collision(asteroid);
+                asteroid.on_collision(projectile);
+
+                let (pid, at) = (asteroid.id, asteroid.b.p);
+                if game.needs_confirm(pid) {
+                    // one prompt at a time, anything else on the list just
+                    // soaks up the shot
+                    if game.phase == Phase::Playing {
+                        if let Some(proc) = game.proc_manager.procs.get(&pid) {
+                            println!("CONFIRM KILL {}? Y to kill, N to spare", proc.summary());
+                        }
+                        game.phase = Phase::ConfirmKill(pid);
+                        game.confirm_hit = Some((at, event.contact.normal));
+                    }
+                    continue;
+                }
+
+                fragments.extend(explode(game, event.b.index));
+                hit.push(pid);
+            }
+            (ColliderType::Player, ColliderType::Asteroid) => {
+                let player = &mut game.player;
+                let asteroid = &mut game.asteroid_manager.asteroids[event.b.index];
+                if player.b.state == BodyState::Dead || asteroid.b.state == BodyState::Dead {
+                    continue;
+                }
+
+                player.on_collision(asteroid);
+                asteroid.on_collision(player);
+
+                game.lives = game.lives.saturating_sub(1);
+                println!("LIVES: {}", game.lives);
+            }
+            _ => {}
+        }
+    }
+
+    finish_hits(game, fragments, hit);
+}
+
+// The player answered the prompt for `pid`'s asteroid hit at `at`: either
+// blow it up like any other hit or bounce it off the shot. Fragments from the
+// same step may have moved it in the vec and the pid may have several pieces,
+// so it is found again as the live piece of `pid` nearest `at`. Nothing moves
+// while the prompt is up, so that is the one that was hit. If it is gone by
+// now there is nothing left to answer for.
+pub fn confirm_kill(
+    game: &mut GameState,
+    pid: Pid,
+    at: [f64; 2],
+    normal: [f64; 2],
+    confirmed: bool,
+) {
+    let distance = |p: [f64; 2]| (p[0] - at[0]).powi(2) + (p[1] - at[1]).powi(2);
+    let index = match game
+        .asteroid_manager
+        .asteroids
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.id == pid && a.b.state == BodyState::Live)
+        .min_by(|(_, a), (_, b)| distance(a.b.p).total_cmp(&distance(b.b.p)))
+    {
+        Some((index, _)) => index,
+        None => return,
+    };
+
+    if !confirmed {
+        let asteroid = &mut game.asteroid_manager.asteroids[index];
+        asteroid.b.v = bounce(asteroid.b.v, normal);
+        return;
+    }
+
+    game.confirmed.insert(pid);
+    let fragments = explode(game, index);
+    finish_hits(game, fragments, vec![pid]);
+}
+
+// Reflect `v` off a surface with unit normal `n` pointing into the body, if
+// the body is moving into it.
+fn bounce(v: [f64; 2], n: [f64; 2]) -> [f64; 2] {
+    let along = v[0] * n[0] + v[1] * n[1];
+    if along >= 0.0 {
+        return v;
+    }
+    [v[0] - 2.0 * along * n[0], v[1] - 2.0 * along * n[1]]
+}
+
+// With --bounce: asteroids that overlap push each other apart and trade
+// momentum like billiard balls, heavier ones (by collider area) giving way
+// less. Pairs come off the grid in index order so a run stays repeatable.
+pub fn bounce_asteroids(asteroids: &mut [Asteroid]) {
+    let grid = SpatialGrid::build(asteroids.iter().map(|a| a.get_collider()));
+    let mut pairs = Vec::new();
+    for (i, a) in asteroids.iter().enumerate() {
+        for j in grid.candidates(a.get_collider()) {
+            if j <= i {
+                continue;
+            }
+            if let Some(contact) = a.contact_with(&asteroids[j]) {
+                pairs.push((i, j, contact));
+            }
+        }
+    }
+
+    for (i, j, contact) in pairs {
+        let (head, tail) = asteroids.split_at_mut(j);
+        let (a, b) = (&mut head[i], &mut tail[0]);
+        let (ma, mb) = (a.c.r * a.c.r, b.c.r * b.c.r);
+        elastic(&mut a.b, ma, &mut b.b, mb, contact);
+        a.c.p = a.b.p;
+        b.c.p = b.b.p;
+    }
+}
+
+// Separate two bodies along the contact normal, which points from `a` to
+// `b`, and swap the part of their velocities along it as an elastic
+// collision between masses `ma` and `mb` would.
+fn elastic(a: &mut MovableBody, ma: f64, b: &mut MovableBody, mb: f64, contact: Contact) {
+    let n = contact.normal;
+    let (ia, ib) = (1.0 / ma, 1.0 / mb);
+    let share = contact.penetration / (ia + ib);
+    a.p = [a.p[0] - n[0] * share * ia, a.p[1] - n[1] * share * ia];
+    b.p = [b.p[0] + n[0] * share * ib, b.p[1] + n[1] * share * ib];
+
+    // already moving apart, e.g. fragments that just split
+    let closing = (b.v[0] - a.v[0]) * n[0] + (b.v[1] - a.v[1]) * n[1];
+    if closing >= 0.0 {
+        return;
+    }
+    let impulse = -2.0 * closing / (ia + ib);
+    a.v = [a.v[0] - n[0] * impulse * ia, a.v[1] - n[1] * impulse * ia];
+    b.v = [b.v[0] + n[0] * impulse * ib, b.v[1] + n[1] * impulse * ib];
+}
+
+// Blow up the asteroid at `index`, splitting it into its process's children
+// if it has any.
+fn explode(game: &mut GameState, index: usize) -> Vec<Asteroid> {
+    let sizing = game.asteroid_manager.sizing;
+    let asteroid = &mut game.asteroid_manager.asteroids[index];
+    let children: Vec<(Pid, AsteroidSizeClass)> = game
+        .proc_manager
+        .procs
+        .values()
+        .filter(|p| p.ppid == asteroid.id)
+        .map(|p| (p.pid, AsteroidSizeClass::for_proc(p, &sizing)))
+        .collect();
+
+    asteroid.explode_asteroid(&children, &mut game.rng)
+}
+
+// a process is only killed once none of its asteroids are left
+fn finish_hits(game: &mut GameState, fragments: Vec<Asteroid>, hit: Vec<Pid>) {
+    game.asteroid_manager.add_fragments(fragments);
+    for pid in hit {
+        if !game.asteroid_manager.has_live(pid) {
+            game.proc_manager.kill_pid(pid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asteroid::{AsteroidManager, SizeThresholds};
+    use collider::Collider;
+    use player::Player;
+    use proc::{self, FixedSource, KillPolicy, ProcFilter, RecordingBackend};
+    use projectile::ProjectileManager;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+    use Phase;
+
+    const WINDOW: [f64; 2] = [800.0, 800.0];
+    const PLAYER: [f64; 2] = [50.0, 50.0];
+
+    // small asteroids at `at`, with pids no real process can have
+    fn game(at: &[[f64; 2]]) -> GameState {
+        let mut asteroid_manager = AsteroidManager::new(WINDOW, SizeThresholds::default());
+        for (i, p) in at.iter().enumerate() {
+            let body = MovableBody::new(*p, WINDOW);
+            let pid = 5_000_000 + i as Pid;
+            let asteroid = Asteroid::new(body, AsteroidSizeClass::Small, pid);
+            asteroid_manager.asteroids.push(asteroid);
+        }
+
+        GameState {
+            phase: Phase::Playing,
+            player: Player::new(
+                MovableBody::new(PLAYER, WINDOW),
+                Collider::new(PLAYER, 10.0),
+            ),
+            asteroid_manager,
+            projectile_manager: ProjectileManager::new(WINDOW),
+            proc_manager: proc::get_proc_manager_with(
+                Box::new(FixedSource(Vec::new())),
+                Box::new(RecordingBackend::default()),
+                KillPolicy::default(),
+                ProcFilter::new(Vec::new()),
+            ),
+            lives: 3,
+            seed: 1,
+            rng: StdRng::seed_from_u64(1),
+            confirm: Vec::new(),
+            confirmed: HashSet::new(),
+            confirm_hit: None,
+        }
+    }
+
+    fn dead(game: &GameState) -> usize {
+        let asteroids = &game.asteroid_manager.asteroids;
+        asteroids
+            .iter()
+            .filter(|a| a.b.state == BodyState::Dead)
+            .count()
+    }
+
+    #[test]
+    fn a_projectile_takes_out_one_asteroid() {
+        let mut game = game(&[[400.0, 400.0], [405.0, 400.0]]);
+        game.projectile_manager
+            .spawn_projectile([400.0, 400.0], 0.0);
+
+        let events = detect_collisions(&game);
+        assert_eq!(events.len(), 2);
+        assert_eq!(dead(&game), 0);
+
+        resolve_collisions(&mut game, events);
+        assert_eq!(dead(&game), 1);
+        assert!(game.projectile_manager.projectiles[0].b.state == BodyState::Dead);
+        assert!(game.player.b.state == BodyState::Live);
+    }
+
+    #[test]
+    fn asteroids_destroy_the_player() {
+        let mut game = game(&[PLAYER, [400.0, 400.0]]);
+
+        let events = detect_collisions(&game);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].a.kind, ColliderType::Player);
+        assert_eq!(events[0].b.index, 0);
+
+        resolve_collisions(&mut game, events);
+        assert!(game.player.b.state == BodyState::Dead);
+        assert_eq!(game.lives, 2);
+        assert_eq!(dead(&game), 0);
+    }
+
+    fn body(p: [f64; 2], v: [f64; 2]) -> MovableBody {
+        let mut b = MovableBody::new(p, WINDOW);
+        b.v = v;
+        b
+    }
+
+    fn momentum(a: &MovableBody, ma: f64, b: &MovableBody, mb: f64) -> [f64; 2] {
+        [ma * a.v[0] + mb * b.v[0], ma * a.v[1] + mb * b.v[1]]
+    }
+
+    fn close(x: [f64; 2], y: [f64; 2]) -> bool {
+        (x[0] - y[0]).abs() < 1e-9 && (x[1] - y[1]).abs() < 1e-9
+    }
+
+    #[test]
+    fn elastic_conserves_momentum_and_separates() {
+        let (ma, mb) = (4.0, 1.0);
+        let mut a = body([100.0, 100.0], [50.0, 10.0]);
+        let mut b = body([115.0, 100.0], [-20.0, 5.0]);
+        let contact = Contact {
+            point: [110.0, 100.0],
+            normal: [1.0, 0.0],
+            penetration: 5.0,
+        };
+        let before = momentum(&a, ma, &b, mb);
+
+        elastic(&mut a, ma, &mut b, mb, contact);
+
+        assert!(close(momentum(&a, ma, &b, mb), before));
+        // the lighter one gives way more, and they just touch now
+        assert!(close(a.p, [99.0, 100.0]));
+        assert!(close(b.p, [119.0, 100.0]));
+        // head on along the normal, the sideways part is untouched
+        assert!(close(a.v, [22.0, 10.0]));
+        assert!(close(b.v, [92.0, 5.0]));
+    }
+
+    #[test]
+    fn bodies_moving_apart_keep_going() {
+        let mut a = body([100.0, 100.0], [-30.0, 0.0]);
+        let mut b = body([100.0, 115.0], [10.0, 20.0]);
+        let contact = Contact {
+            point: [100.0, 110.0],
+            normal: [0.0, 1.0],
+            penetration: 4.0,
+        };
+
+        elastic(&mut a, 1.0, &mut b, 1.0, contact);
+
+        assert_eq!(a.v, [-30.0, 0.0]);
+        assert_eq!(b.v, [10.0, 20.0]);
+        assert!(close(a.p, [100.0, 98.0]));
+        assert!(close(b.p, [100.0, 117.0]));
+    }
+
+    #[test]
+    fn overlapping_asteroids_bounce_apart() {
+        let mut asteroids = vec![
+            Asteroid::new(
+                body([400.0, 400.0], [40.0, 0.0]),
+                AsteroidSizeClass::Large,
+                10,
+            ),
+            Asteroid::new(
+                body([430.0, 410.0], [-40.0, 0.0]),
+                AsteroidSizeClass::Medium,
+                11,
+            ),
+        ];
+        let mass = |a: &Asteroid| a.c.r * a.c.r;
+        let (ma, mb) = (mass(&asteroids[0]), mass(&asteroids[1]));
+        let before = momentum(&asteroids[0].b, ma, &asteroids[1].b, mb);
+        assert!(asteroids[0].contact_with(&asteroids[1]).is_some());
+
+        bounce_asteroids(&mut asteroids);
+
+        let after = momentum(&asteroids[0].b, ma, &asteroids[1].b, mb);
+        assert!(close(after, before));
+        assert!(asteroids[0].b.v != [40.0, 0.0]);
+        assert!(asteroids[0]
+            .contact_with(&asteroids[1])
+            .is_none_or(|c| c.penetration < 1e-9));
+    }
+}
@@ -0,0 +1,261 @@
+use regex::Regex;
+use std::time;
+
+use asteroid::{SizeMetric, SizeThresholds};
+use label::LabelMode;
+use proc::{KillMode, KillPolicy, NameRule, Scope};
+
+// Startup options, read from the command line.
+//
+//   --live-fire               really signal processes (default is dry run)
+//   --grace MS                how long SIGTERM gets before SIGKILL
+//   --protect NAME            never target processes with this name
+//   --protect-regex PATTERN   never target processes whose name matches
+//   --cgroup PATH             only processes in this cgroup or below it
+//   --unit NAME               only processes in this systemd unit
+//   --pidns PID               only processes in the same pid namespace as PID
+//   --user UID|NAME           only processes of this user instead of our own
+//   --name REGEX              only processes whose name matches
+//   --descendants-of PID      only processes started by PID, directly or not
+//   --confirm NAME            ask before killing processes with this name
+//   --confirm-regex PATTERN   ask before killing processes whose name matches
+//   --no-confirm              forget the confirm list so far, including defaults
+//   --size-by memory|cpu      what makes an asteroid big
+//   --medium N, --large N     size thresholds in bytes or cpu seconds
+//   --bounce                  asteroids bounce off each other instead of overlapping
+//   --labels off|names|details  what is written under each asteroid, L cycles
+//   --font FILE               ttf font for the labels
+//   --seed N                  seed for every random decision, random if unset
+//   --headless TICKS          run TICKS steps without a window and print the state
+//   --script FILE             inputs for the headless run, `<tick> <input>` per line
+//   --scenario FILE           made up processes from a json file instead of real ones
+//   --record FILE             save inputs and process events to FILE on exit
+//   --replay FILE             play back a recording instead of real processes
+// shells, editors and databases, killing one of these by accident hurts
+const DEFAULT_CONFIRM: [&str; 13] = [
+    "bash",
+    "zsh",
+    "fish",
+    "sh",
+    "tmux",
+    "screen",
+    "vim",
+    "nvim",
+    "emacs",
+    "postgres",
+    "mysqld",
+    "redis-server",
+    "mongod",
+];
+
+pub struct Config {
+    pub kill_mode: KillMode,
+    pub kill_policy: KillPolicy,
+    pub protected: Vec<NameRule>,
+    pub confirm: Vec<NameRule>,
+    pub scope: Vec<Scope>,
+    pub sizing: SizeThresholds,
+    pub bounce: bool,
+    pub labels: LabelMode,
+    pub font: Option<String>,
+    pub seed: Option<u64>,
+    pub headless: Option<u64>,
+    pub script: Option<String>,
+    pub scenario: Option<String>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            kill_mode: KillMode::DryRun,
+            kill_policy: KillPolicy::default(),
+            protected: Vec::new(),
+            confirm: DEFAULT_CONFIRM
+                .iter()
+                .map(|name| NameRule::Name(name.to_string()))
+                .collect(),
+            scope: Vec::new(),
+            sizing: SizeThresholds::default(),
+            bounce: false,
+            labels: LabelMode::Names,
+            font: None,
+            seed: None,
+            headless: None,
+            script: None,
+            scenario: None,
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Config {
+        let mut config = Config::default();
+        let mut medium = None;
+        let mut large = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--live-fire" => config.kill_mode = KillMode::LiveFire,
+                "--grace" => {
+                    if let Some(ms) = parse_value(&arg, args.next()) {
+                        config.kill_policy.grace = time::Duration::from_millis(ms as u64);
+                    }
+                }
+                "--protect" => {
+                    if let Some(name) = args.next() {
+                        config.protected.push(NameRule::Name(name));
+                    }
+                }
+                "--protect-regex" => {
+                    if let Some(pattern) = args.next() {
+                        match Regex::new(&pattern) {
+                            Ok(re) => config.protected.push(NameRule::Pattern(re)),
+                            Err(e) => println!("ignoring --protect-regex {}: {}", pattern, e),
+                        }
+                    }
+                }
+                "--confirm" => {
+                    if let Some(name) = args.next() {
+                        config.confirm.push(NameRule::Name(name));
+                    }
+                }
+                "--confirm-regex" => {
+                    if let Some(pattern) = args.next() {
+                        match Regex::new(&pattern) {
+                            Ok(re) => config.confirm.push(NameRule::Pattern(re)),
+                            Err(e) => println!("ignoring --confirm-regex {}: {}", pattern, e),
+                        }
+                    }
+                }
+                "--no-confirm" => config.confirm.clear(),
+                "--cgroup" => {
+                    if let Some(path) = args.next() {
+                        config.scope.push(Scope::Cgroup(path));
+                    }
+                }
+                "--unit" => {
+                    if let Some(name) = args.next() {
+                        config.scope.push(Scope::unit(&name));
+                    }
+                }
+                "--pidns" => match args.next().map(|s| s.parse()) {
+                    Some(Ok(pid)) => match Scope::pid_namespace_of(pid) {
+                        Some(scope) => config.scope.push(scope),
+                        None => println!("ignoring --pidns {}: can't read its namespace", pid),
+                    },
+                    other => println!("ignoring --pidns {:?}", other),
+                },
+                "--user" => {
+                    if let Some(user) = args.next() {
+                        match Scope::user(&user) {
+                            Some(scope) => config.scope.push(scope),
+                            None => println!("ignoring --user {}: no such user", user),
+                        }
+                    }
+                }
+                "--name" => {
+                    if let Some(pattern) = args.next() {
+                        match Regex::new(&pattern) {
+                            Ok(re) => config.scope.push(Scope::Name(re)),
+                            Err(e) => println!("ignoring --name {}: {}", pattern, e),
+                        }
+                    }
+                }
+                "--descendants-of" => match args.next().map(|s| s.parse()) {
+                    Some(Ok(pid)) => config.scope.push(Scope::DescendantOf(pid)),
+                    other => println!("ignoring --descendants-of {:?}", other),
+                },
+                "--size-by" => match args.next().as_deref() {
+                    Some("memory") => config.sizing = SizeThresholds::new(SizeMetric::Memory),
+                    Some("cpu") => config.sizing = SizeThresholds::new(SizeMetric::CpuTime),
+                    other => println!("ignoring --size-by {:?}", other),
+                },
+                "--bounce" => config.bounce = true,
+                "--labels" => match args.next().as_deref() {
+                    Some("off") => config.labels = LabelMode::Off,
+                    Some("names") => config.labels = LabelMode::Names,
+                    Some("details") => config.labels = LabelMode::Details,
+                    other => println!("ignoring --labels {:?}", other),
+                },
+                "--font" => config.font = args.next(),
+                "--seed" => match args.next().map(|s| s.parse()) {
+                    Some(Ok(seed)) => config.seed = Some(seed),
+                    other => println!("ignoring --seed {:?}", other),
+                },
+                "--headless" => config.headless = parse_value(&arg, args.next()).map(|n| n as u64),
+                "--script" => config.script = args.next(),
+                "--scenario" => config.scenario = args.next(),
+                "--record" => config.record = args.next(),
+                "--replay" => config.replay = args.next(),
+                "--medium" => medium = parse_value(&arg, args.next()),
+                "--large" => large = parse_value(&arg, args.next()),
+                _ => {}
+            }
+        }
+
+        // thresholds apply to whichever metric was picked
+        if let Some(n) = medium {
+            config.sizing.medium = n;
+        }
+        if let Some(n) = large {
+            config.sizing.large = n;
+        }
+
+        config
+    }
+}
+
+fn parse_value(flag: &str, value: Option<String>) -> Option<f64> {
+    let value = value?;
+    match value.parse() {
+        Ok(n) => Some(n),
+        Err(e) => {
+            println!("ignoring {} {}: {}", flag, value, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Config {
+        Config::from_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn defaults_are_safe() {
+        let config = parse("");
+        assert_eq!(config.kill_mode, KillMode::DryRun);
+        assert!(config.protected.is_empty());
+        assert_eq!(config.sizing.metric, SizeMetric::Memory);
+    }
+
+    #[test]
+    fn thresholds_apply_to_the_picked_metric() {
+        // in either order
+        for args in &[
+            "--size-by cpu --medium 5 --large 50",
+            "--large 50 --medium 5 --size-by cpu",
+        ] {
+            let config = parse(args);
+            assert_eq!(config.sizing.metric, SizeMetric::CpuTime);
+            assert_eq!(config.sizing.medium, 5.0);
+            assert_eq!(config.sizing.large, 50.0);
+        }
+    }
+
+    #[test]
+    fn bad_values_are_ignored() {
+        let config = parse("--grace soon --medium lots --protect-regex ( --size-by disk");
+        assert_eq!(config.kill_policy.grace, KillPolicy::default().grace);
+        assert_eq!(config.sizing.medium, SizeThresholds::default().medium);
+        assert!(config.protected.is_empty());
+        assert_eq!(config.sizing.metric, SizeMetric::Memory);
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use config::Config;
+use input::GameInput;
+use proc::KillMode;
+use simulation::Simulation;
+
+const HEADLESS_WINDOW_SIZE: [f64; 2] = [800.0, 800.0];
+
+// A script is one `<tick> <input>` per line, e.g. `0 start` or `30 fire`.
+// Blank lines and lines starting with `#` are ignored.
+pub fn load_script(path: &str) -> Result<Vec<(u64, GameInput)>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut script = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (tick, input) = match line.find(char::is_whitespace) {
+            Some(i) => line.split_at(i),
+            None => return Err(format!("{}:{}: expected '<tick> <input>'", path, n + 1)),
+        };
+        let tick = tick
+            .parse()
+            .map_err(|e| format!("{}:{}: bad tick '{}': {}", path, n + 1, tick, e))?;
+        let input = input
+            .parse()
+            .map_err(|e| format!("{}:{}: {}", path, n + 1, e))?;
+        script.push((tick, input));
+    }
+
+    script.sort_by_key(|(tick, _)| *tick);
+    Ok(script)
+}
+
+// Run `ticks` fixed steps without a window, feeding in the scripted inputs,
+// then print the resulting state. Never signals real processes. With
+// --replay the script is ignored in favour of the recorded inputs.
+pub fn run(mut config: Config, dt: f64, ticks: u64, script: Option<String>) {
+    if config.kill_mode == KillMode::LiveFire {
+        println!("ignoring --live-fire in headless mode");
+        config.kill_mode = KillMode::DryRun;
+    }
+
+    let script = match script {
+        Some(path) => match load_script(&path) {
+            Ok(script) => script,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let record = config.record.clone();
+    let mut sim = match Simulation::from_config(config, HEADLESS_WINDOW_SIZE, dt) {
+        Ok(sim) => sim,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    drive(&mut sim, script, ticks);
+
+    println!("{}", sim.summary());
+    if let Some(path) = record {
+        sim.save_recording(&path);
+    }
+}
+
+// Step `sim` until it reaches tick `ticks`, handing it each scripted input
+// on its tick.
+pub fn drive(sim: &mut Simulation, script: Vec<(u64, GameInput)>, ticks: u64) {
+    let mut inputs = script.into_iter().peekable();
+    while sim.tick < ticks {
+        while let Some((_, input)) = inputs.next_if(|(tick, _)| *tick <= sim.tick) {
+            println!("tick={} input={}", sim.tick, input);
+            sim.handle_input(input);
+        }
+        sim.step();
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use player::RotationState;
+
+// Everything the player can tell the game to do, independent of piston's
+// keyboard events so the simulation can be driven from a script.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum GameInput {
+    Thrust(bool),
+    Rotate(RotationState),
+    Fire,
+    Start,         // leave the title or game over screen
+    Pause,         // toggle pause
+    Restart,       // new round from the pause screen
+    Confirm(bool), // answer a kill confirmation
+}
+
+// Inputs are written one per line as e.g. `thrust on`, `rotate left`,
+// `fire` or `confirm yes`.
+impl FromStr for GameInput {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GameInput, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["thrust", "on"] => Ok(GameInput::Thrust(true)),
+            ["thrust", "off"] => Ok(GameInput::Thrust(false)),
+            ["rotate", "left"] => Ok(GameInput::Rotate(RotationState::Positive)),
+            ["rotate", "right"] => Ok(GameInput::Rotate(RotationState::Negative)),
+            ["rotate", "none"] => Ok(GameInput::Rotate(RotationState::None)),
+            ["fire"] => Ok(GameInput::Fire),
+            ["start"] => Ok(GameInput::Start),
+            ["pause"] => Ok(GameInput::Pause),
+            ["restart"] => Ok(GameInput::Restart),
+            ["confirm", "yes"] => Ok(GameInput::Confirm(true)),
+            ["confirm", "no"] => Ok(GameInput::Confirm(false)),
+            _ => Err(format!("unknown input '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for GameInput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameInput::Thrust(true) => write!(f, "thrust on"),
+            GameInput::Thrust(false) => write!(f, "thrust off"),
+            GameInput::Rotate(RotationState::Positive) => write!(f, "rotate left"),
+            GameInput::Rotate(RotationState::Negative) => write!(f, "rotate right"),
+            GameInput::Rotate(RotationState::None) => write!(f, "rotate none"),
+            GameInput::Fire => write!(f, "fire"),
+            GameInput::Start => write!(f, "start"),
+            GameInput::Pause => write!(f, "pause"),
+            GameInput::Restart => write!(f, "restart"),
+            GameInput::Confirm(true) => write!(f, "confirm yes"),
+            GameInput::Confirm(false) => write!(f, "confirm no"),
+        }
+    }
+}
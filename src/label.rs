@@ -0,0 +1,152 @@
+use graphics::*;
+use opengl_graphics::{GlGraphics, GlyphCache, TextureSettings};
+use std::path::Path;
+
+use asteroid::Asteroid;
+use movable_body::BodyState;
+use proc::Proc;
+
+const LABEL_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const LABEL_SIZE: types::FontSize = 11;
+const LINE_HEIGHT: f64 = 13.0;
+
+// tried in order when no --font is given
+const FONT_PATHS: [&str; 4] = [
+    "assets/font.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/System/Library/Fonts/Menlo.ttc",
+];
+
+// How much is written under each asteroid. Cycled with L.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LabelMode {
+    Off,
+    Names,   // pid and name
+    Details, // pid and name, then cpu and memory
+}
+
+impl LabelMode {
+    pub fn next(self) -> LabelMode {
+        match self {
+            LabelMode::Off => LabelMode::Names,
+            LabelMode::Names => LabelMode::Details,
+            LabelMode::Details => LabelMode::Off,
+        }
+    }
+}
+
+pub struct Labels {
+    pub mode: LabelMode,
+    glyphs: GlyphCache<'static>,
+}
+
+impl Labels {
+    // Load `font`, or the first font found in FONT_PATHS. Without a font the
+    // game runs unlabelled.
+    pub fn load(font: Option<&str>, mode: LabelMode) -> Option<Labels> {
+        let paths: Vec<&str> = match font {
+            Some(path) => vec![path],
+            None => FONT_PATHS.to_vec(),
+        };
+
+        for path in paths {
+            if !Path::new(path).exists() {
+                continue;
+            }
+            match GlyphCache::new(path, (), TextureSettings::new()) {
+                Ok(glyphs) => return Some(Labels { mode, glyphs }),
+                Err(e) => println!("can't load font {}: {}", path, e),
+            }
+        }
+
+        println!("no font found, pass --font FILE for process labels");
+        None
+    }
+
+    // write what the asteroid is just below it, upright whatever its rotation
+    pub fn render(&mut self, asteroid: &Asteroid, proc: &Proc, ctx: &Context, gl: &mut GlGraphics) {
+        if self.mode == LabelMode::Off || asteroid.b.state == BodyState::Dead {
+            return;
+        }
+
+        let mut lines = vec![format!("{} {}", proc.pid, proc.name)];
+        if self.mode == LabelMode::Details {
+            lines.push(format!(
+                "{:.1}% {}",
+                proc.cpu_percent,
+                format_bytes(proc.rss)
+            ));
+        }
+
+        let x = asteroid.b.p[0] - asteroid.c.r;
+        let y = asteroid.b.p[1] + asteroid.c.r + LINE_HEIGHT;
+        self.render_lines(&lines, [x, y], ctx, gl);
+    }
+
+    // the kill confirmation, in the top left corner whatever the label mode
+    pub fn render_prompt(&mut self, proc: &Proc, ctx: &Context, gl: &mut GlGraphics) {
+        let lines = [
+            format!("Kill {}?", proc.summary()),
+            "Y to kill, N to spare".to_string(),
+        ];
+        self.render_lines(&lines, [LINE_HEIGHT, 2.0 * LINE_HEIGHT], ctx, gl);
+    }
+
+    fn render_lines(&mut self, lines: &[String], at: [f64; 2], ctx: &Context, gl: &mut GlGraphics) {
+        let mut y = at[1];
+        for line in lines {
+            let transform = ctx.transform.trans(at[0], y);
+            let _ = text(
+                LABEL_COLOR,
+                LABEL_SIZE,
+                line,
+                &mut self.glyphs,
+                transform,
+                gl,
+            );
+            y += LINE_HEIGHT;
+        }
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "K", "M", "G"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.0}{}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_in_the_largest_whole_unit() {
+        assert_eq!(format_bytes(0), "0B");
+        assert_eq!(format_bytes(1023), "1023B");
+        assert_eq!(format_bytes(1024), "1K");
+        assert_eq!(format_bytes(300 * 1024 * 1024), "300M");
+        assert_eq!(format_bytes(5 << 30), "5G");
+        // nothing past gigabytes
+        assert_eq!(format_bytes(2048 << 30), "2048G");
+    }
+
+    #[test]
+    fn modes_cycle_back_to_off() {
+        let mut mode = LabelMode::Off;
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            mode = mode.next();
+            seen.push(mode);
+        }
+        assert_eq!(
+            seen,
+            vec![LabelMode::Names, LabelMode::Details, LabelMode::Off]
+        );
+    }
+}
@@ -1,46 +1,59 @@
 extern crate fps_counter;
 extern crate glutin_window;
 extern crate graphics;
+extern crate libc;
 extern crate libproc;
 extern crate opengl_graphics;
 extern crate piston;
 extern crate rand;
+extern crate regex;
+extern crate serde;
+extern crate serde_json;
 extern crate vecmath;
 
 mod asteroid;
 mod collider;
+mod collision;
+mod config;
+mod headless;
+mod input;
+mod label;
 mod movable_body;
 mod player;
 mod proc;
 mod projectile;
+mod replay;
+mod simulation;
 mod traits;
 
 use glutin_window::GlutinWindow as GWindow;
-use movable_body::MovableBody;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::event_loop::{EventSettings, Events};
 use piston::*;
 
-use asteroid::*;
-use collider::*;
-use player::*;
+use config::Config;
+use input::GameInput;
+use label::Labels;
 use proc::*;
-use projectile::*;
+use simulation::*;
 use traits::*;
 
 const BLACK: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
-const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
 
-pub struct GameState {
-    pub player: Player,
-    pub asteroid_manager: AsteroidManager,
-    pub projectile_manager: ProjectileManager,
-    pub proc_manager: ProcManager,
-}
+const TITLE_OVERLAY: [f32; 4] = [0.0, 0.0, 0.3, 0.3];
+const PAUSED_OVERLAY: [f32; 4] = [0.5, 0.5, 0.5, 0.4];
+const GAME_OVER_OVERLAY: [f32; 4] = [0.6, 0.0, 0.0, 0.3];
+const CONFIRM_OVERLAY: [f32; 4] = [0.8, 0.6, 0.0, 0.3];
+const CONFIRM_MARKER: [f32; 4] = [0.8, 0.0, 0.0, 1.0];
+
+// the simulation always advances in steps of this size
+const TICK_DT: f64 = 1.0 / 60.0;
 
 pub struct App {
     gl: GlGraphics, // OpenGL drawing backend.
-    rotation: f64,  // Rotation for the square.
+    lag: f64,       // real time not yet simulated
+    labels: Option<Labels>,
+    #[allow(dead_code)]
     fps: fps_counter::FPSCounter,
 }
 
@@ -48,14 +61,15 @@ impl App {
     fn render(&mut self, args: &RenderArgs, game: &GameState) {
         use graphics::*;
 
-        let (x, y) = (args.window_size[0] / 2.0, args.window_size[1] / 2.0);
-
+        let labels = &mut self.labels;
         self.gl.draw(args.viewport(), |c, gl| {
             // Clear the screen.
             clear(BLACK, gl);
 
-            game.player.render(&c, gl);
-            game.player.render_debug(&c, gl);
+            if game.phase != Phase::Title {
+                game.player.render(&c, gl);
+                game.player.render_debug(&c, gl);
+            }
 
             for projectile in game.projectile_manager.projectiles.iter() {
                 projectile.render(&c, gl);
@@ -66,77 +80,116 @@ impl App {
                 asteroid.render(&c, gl);
                 asteroid.render_debug(&c, gl);
             }
-        });
-    }
 
-    fn update(&mut self, args: &UpdateArgs, game: &mut GameState) {
-        game.projectile_manager.purge();
+            if let Some(labels) = labels.as_mut() {
+                for asteroid in game.asteroid_manager.asteroids.iter() {
+                    if let Some(proc) = game.proc_manager.procs.get(&asteroid.id) {
+                        labels.render(asteroid, proc, &c, gl);
+                    }
+                }
+            }
 
-        // Rotate 2 radians per second.
-        self.rotation += 2.0 * args.dt;
-        game.player.update(args.dt);
+            // tint the whole field for anything that isn't live play
+            let overlay = match game.phase {
+                Phase::Title => Some(TITLE_OVERLAY),
+                Phase::Playing => None,
+                Phase::Paused => Some(PAUSED_OVERLAY),
+                Phase::GameOver => Some(GAME_OVER_OVERLAY),
+                Phase::ConfirmKill(_) => Some(CONFIRM_OVERLAY),
+            };
+            if let Some(color) = overlay {
+                rectangle(
+                    color,
+                    [0.0, 0.0, args.window_size[0], args.window_size[1]],
+                    c.transform,
+                    gl,
+                );
+            }
 
-        for projectile in game.projectile_manager.projectiles.iter_mut() {
-            projectile.update(args.dt);
-        }
+            // ring the asteroid the prompt is about, so the question can
+            // be answered even without a font for the text
+            if let Phase::ConfirmKill(pid) = game.phase {
+                for asteroid in game.asteroid_manager.asteroids.iter() {
+                    if asteroid.id == pid && asteroid.b.state == movable_body::BodyState::Live {
+                        let r = asteroid.c.r + 6.0;
+                        circle_arc(
+                            CONFIRM_MARKER,
+                            2.0,
+                            0.0,
+                            2.0 * std::f64::consts::PI,
+                            [-r, -r, 2.0 * r, 2.0 * r],
+                            c.transform.trans(asteroid.b.p[0], asteroid.b.p[1]),
+                            gl,
+                        );
+                    }
+                }
 
-        for asteroid in game.asteroid_manager.asteroids.iter_mut() {
-            asteroid.update(args.dt)
+                if let (Some(labels), Some(proc)) = (labels, game.proc_manager.procs.get(&pid)) {
+                    labels.render_prompt(proc, &c, gl);
+                }
+            }
+        });
+    }
+
+    // run as many fixed steps as the elapsed time covers
+    fn update(&mut self, args: &UpdateArgs, sim: &mut Simulation) {
+        self.lag += args.dt;
+        while self.lag >= sim.dt {
+            sim.step();
+            self.lag -= sim.dt;
         }
+    }
 
-        let mut collisions: Vec<(&mut Projectile, &mut Asteroid)> = Vec::new();
-        for projectile in game.projectile_manager.projectiles.iter_mut() {
-            for asteroid in game.asteroid_manager.asteroids.iter_mut() {
-                if asteroid.collides_with(projectile) {
-                    // println!("Player COLLISION");
-                    // collisions.push((projectile, asteroid));
-                    // let res = asteroid.explode_asteroid();
-                    if let Some(new_asteroids) = asteroid.explode_asteroid() {
-                        // push new_asteroids somewhere to be added outside of this iter_mut
-                    };
-                    projectile.destroy();
-                };
+    fn handle_input(&mut self, args: &ButtonArgs, sim: &mut Simulation) {
+        // labels are only drawn, the simulation doesn't need to know
+        if let (ButtonState::Press, Button::Keyboard(Key::L)) = (args.state, args.button) {
+            if let Some(labels) = &mut self.labels {
+                labels.mode = labels.mode.next();
             }
+            return;
         }
 
-        game.proc_manager.poll_pids();
+        let input = match (args.state, args.button) {
+            (ButtonState::Press, Button::Keyboard(Key::W)) => GameInput::Thrust(true),
+            (ButtonState::Release, Button::Keyboard(Key::W)) => GameInput::Thrust(false),
+            (ButtonState::Press, Button::Keyboard(Key::A)) => {
+                GameInput::Rotate(player::RotationState::Positive)
+            }
+            (ButtonState::Press, Button::Keyboard(Key::D)) => {
+                GameInput::Rotate(player::RotationState::Negative)
+            }
+            (ButtonState::Release, Button::Keyboard(Key::A))
+            | (ButtonState::Release, Button::Keyboard(Key::D)) => {
+                GameInput::Rotate(player::RotationState::None)
+            }
+            (ButtonState::Press, Button::Keyboard(Key::Space)) => GameInput::Fire,
+            (ButtonState::Press, Button::Keyboard(Key::Return)) => GameInput::Start,
+            (ButtonState::Press, Button::Keyboard(Key::P)) => GameInput::Pause,
+            (ButtonState::Press, Button::Keyboard(Key::R)) => GameInput::Restart,
+            (ButtonState::Press, Button::Keyboard(Key::Y)) => GameInput::Confirm(true),
+            (ButtonState::Press, Button::Keyboard(Key::N)) => GameInput::Confirm(false),
+            _ => return,
+        };
+
+        sim.handle_input(input);
+    }
+}
 
-        // TODO resolve collisions here ( remove projectiles/asteroids or end game)
+fn main() {
+    let config = Config::from_args(std::env::args().skip(1));
 
-        // for &mut (p, a) in collisions.iter_mut() {
-        //     // destory projectile
-        //     // game.asteroid_manager.explode_asteroid(a)
-        //     a.explode_asteroid();
-        // }
+    if let Some(ticks) = config.headless {
+        let script = config.script.clone();
+        headless::run(config, TICK_DT, ticks, script);
+        return;
     }
 
-    fn handle_input(&mut self, args: &ButtonArgs, game: &mut GameState) {
-        match args.state {
-            ButtonState::Press => match args.button {
-                Button::Keyboard(Key::W) => game.player.set_thrust(true),
-                Button::Keyboard(Key::A) => {
-                    game.player.set_rotation(player::RotationState::Positive)
-                }
-                Button::Keyboard(Key::D) => {
-                    game.player.set_rotation(player::RotationState::Negative)
-                }
-                Button::Keyboard(Key::Space) => {
-                    game.player.shoot_projectile(&mut game.projectile_manager)
-                }
-                _ => {}
-            },
-            ButtonState::Release => match args.button {
-                Button::Keyboard(Key::W) => game.player.set_thrust(false),
-                Button::Keyboard(Key::A) | Button::Keyboard(Key::D) => {
-                    game.player.set_rotation(player::RotationState::None)
-                }
-                _ => {}
-            },
-        }
+    match config.kill_mode {
+        KillMode::LiveFire => println!("LIVE FIRE: shooting an asteroid will kill its process"),
+        KillMode::DryRun => println!("DRY RUN: pass --live-fire to really kill processes"),
     }
-}
+    println!("Press Enter to start, P to pause, L to cycle process labels");
 
-fn main() {
     // Change this to OpenGL::V2_1 if not working.
     let opengl = OpenGL::V3_2;
 
@@ -150,44 +203,42 @@ fn main() {
     // Create a new game and run it.
     let mut app = App {
         gl: GlGraphics::new(opengl),
-        rotation: 0.0,
+        lag: 0.0,
+        labels: Labels::load(config.font.as_deref(), config.labels),
         fps: fps_counter::FPSCounter::new(),
     };
 
-    let initial_position = [window.size().width / 2.0, window.size().height / 2.0];
-    let player_radius = 10.0;
-
-    let collider = Collider::new(initial_position, player_radius);
-    let mb = MovableBody::new(
-        initial_position,
-        [window.size().width, window.size().height],
-    );
-
-    let pm = ProjectileManager::new([window.size().width, window.size().height]);
-    let am = AsteroidManager::new([window.size().width, window.size().height]);
-    let procm = proc::get_proc_manager();
+    if app.labels.is_none() && !config.confirm.is_empty() {
+        println!("WARNING: no font, kill confirmations only ring the asteroid on screen and");
+        println!("print the process here. Pass --font FILE, or --no-confirm to skip them.");
+    }
 
-    let mut game = GameState {
-        player: Player::new(mb, collider),
-        asteroid_manager: am,
-        projectile_manager: pm,
-        proc_manager: procm,
+    let window_size = [window.size().width, window.size().height];
+    let record = config.record.clone();
+    let mut sim = match Simulation::from_config(config, window_size, TICK_DT) {
+        Ok(sim) => sim,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
     };
 
-    println!("{:?}", get_procs());
-
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
         if let Some(args) = e.render_args() {
-            app.render(&args, &game);
+            app.render(&args, &sim.game);
         }
 
         if let Some(args) = e.update_args() {
-            app.update(&args, &mut game);
+            app.update(&args, &mut sim);
         }
 
         if let Some(args) = e.button_args() {
-            app.handle_input(&args, &mut game);
+            app.handle_input(&args, &mut sim);
         }
     }
+
+    if let Some(path) = record {
+        sim.save_recording(&path);
+    }
 }
@@ -1,16 +1,15 @@
 use graphics::*;
 use opengl_graphics::GlGraphics;
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
-use std::rc::Rc;
 use vecmath::*;
 
-use asteroid::*;
 use collider::*;
 use movable_body::*;
 use projectile::*;
 use traits::*;
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum RotationState {
     Positive,
     Negative,
@@ -22,6 +21,8 @@ pub struct Player {
     pub b: MovableBody,
     pub thrust: bool,
     pub rotating: RotationState,
+    // seconds left with the collider switched off
+    pub invulnerable: f64,
     // pm: Rc<ProjectileManager>,
 }
 
@@ -30,12 +31,29 @@ const C2: [f32; 4] = [1.0, 1.0, 1.0, 0.4];
 const C3: [f32; 4] = [0.0, 0.0, 0.0, 0.4];
 const ROTATION_VELOCITY: f64 = 2.4;
 const THRUST_FORCE: f64 = 100.0;
+const INVULNERABLE_TIME: f64 = 3.0;
+const BLINK_RATE: f64 = 8.0; // blinks per second while invulnerable
+
+// drawn and collided with, around the player's position
+pub const PLAYER_SHAPE: [Vector2<f64>; 3] = [[-10.0, -10.0], [10.0, -10.0], [0.0, 10.0]];
 
 impl GameObject for Player {
     fn update(&mut self, dt: f64) {
+        if self.b.state == BodyState::Dead {
+            return;
+        }
+
+        if self.invulnerable > 0.0 {
+            self.invulnerable -= dt;
+            if self.invulnerable <= 0.0 {
+                self.invulnerable = 0.0;
+                self.c.on();
+            }
+        }
+
         match self.rotating {
             RotationState::Positive => self.b.rv = ROTATION_VELOCITY,
-            RotationState::Negative => self.b.rv = -1.0 * ROTATION_VELOCITY,
+            RotationState::Negative => self.b.rv = -ROTATION_VELOCITY,
             RotationState::None => self.b.rv = 0.0,
         }
 
@@ -47,24 +65,30 @@ impl GameObject for Player {
         self.b.apply_force(force_vector);
         self.b.update(dt);
         self.c.p = self.b.p;
+        self.c.angle = -self.b.r;
 
         // println!("{}, {}", self.b.p[0], self.b.p[1])
         // println!("{}", self.thrust);
     }
     fn render(&self, ctx: &Context, gl: &mut GlGraphics) {
-        let verts: [Vector2<f64>; 3] = [[0.0, 0.0], [20.0, 0.0], [10.0, 20.0]];
+        if self.b.state == BodyState::Dead {
+            return;
+        }
+        // blink while invulnerable
+        if (self.invulnerable * BLINK_RATE) as u32 % 2 == 1 {
+            return;
+        }
         let transform = ctx
             .transform
             .trans(self.b.p[0], self.b.p[1])
-            .rot_rad(-self.b.r)
-            .trans(-10.0, -10.0);
+            .rot_rad(-self.b.r);
 
-        // Draw a box rotating around the middle of the screen.
-        // rectangle(RED, square, transform, gl);
-
-        polygon(RED, &verts[..], transform, gl)
+        polygon(RED, &PLAYER_SHAPE[..], transform, gl)
     }
     fn render_debug(&self, ctx: &Context, gl: &mut GlGraphics) {
+        if self.b.state == BodyState::Dead {
+            return;
+        }
         let transform = ctx.transform.trans(self.b.p[0], self.b.p[1]);
         line(
             RED,
@@ -95,19 +119,13 @@ impl GameObject for Player {
 }
 
 impl Collides for Player {
-    fn collides_with<C: Collides>(&self, other: &C) -> bool {
-        self.c.are_colliding(other.get_collider())
-    }
-
     fn get_collider(&self) -> &Collider {
         &self.c
     }
 
     fn on_collision<C: Collides>(&mut self, other: &C) {
-        let t = other.collider_type();
-        match t {
-            ColliderType::Asteroid => (), // trigger end game,
-            _ => (),
+        if let ColliderType::Asteroid = other.collider_type() {
+            self.destroy();
         }
     }
 
@@ -129,20 +147,79 @@ impl Player {
             c: collider,
             thrust: false,
             rotating: RotationState::None,
+            invulnerable: 0.0,
             // pm: projectile_manager,
         }
     }
 
+    pub fn destroy(&mut self) {
+        self.b.state = BodyState::Dead;
+        self.c.off();
+    }
+
+    // bring the player back at `p`, at rest and with the collider off for
+    // a few seconds
+    pub fn respawn(&mut self, p: Vector2<f64>) {
+        self.b.p = p;
+        self.b.v = [0.0, 0.0];
+        self.b.a = [0.0, 0.0];
+        self.b.r = 0.0;
+        self.b.state = BodyState::Live;
+        self.c.p = p;
+        self.c.off();
+        self.invulnerable = INVULNERABLE_TIME;
+    }
+
     pub fn set_thrust(&mut self, b: bool) {
         self.thrust = b;
     }
 
     pub fn set_rotation(&mut self, state: RotationState) {
         self.rotating = state;
-        println!("{:?}", self.rotating)
     }
 
     pub fn shoot_projectile(&self, projectile_manager: &mut ProjectileManager) {
+        if self.b.state == BodyState::Dead {
+            return;
+        }
         projectile_manager.spawn_projectile(self.b.p, self.b.r);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENTRE: [f64; 2] = [400.0, 300.0];
+
+    fn player() -> Player {
+        let body = MovableBody::new([50.0, 50.0], [800.0, 600.0]);
+        Player::new(body, Collider::new([50.0, 50.0], 10.0))
+    }
+
+    #[test]
+    fn respawn_is_invulnerable_for_a_while() {
+        let rock = Collider::new(CENTRE, 10.0);
+        let mut player = player();
+        player.destroy();
+        player.respawn(CENTRE);
+        assert!(player.b.state == BodyState::Live);
+        assert!(!player.c.are_colliding(&rock));
+
+        player.update(INVULNERABLE_TIME / 2.0);
+        assert!(!player.c.are_colliding(&rock));
+
+        player.update(INVULNERABLE_TIME);
+        assert_eq!(player.invulnerable, 0.0);
+        assert!(player.c.are_colliding(&rock));
+    }
+
+    #[test]
+    fn dead_players_stay_put() {
+        let mut player = player();
+        player.b.v = [10.0, 0.0];
+        player.destroy();
+        player.update(1.0);
+        assert_eq!(player.b.p, [50.0, 50.0]);
+    }
+}
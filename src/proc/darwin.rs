@@ -0,0 +1,67 @@
+use libc::c_char;
+use libproc::libproc::bsd_info::BSDInfo;
+use libproc::libproc::proc_pid::{pidinfo, pidpath};
+use libproc::libproc::task_info::TaskInfo;
+use std::time;
+
+use super::{Pid, Proc, ProcState};
+
+// from <sys/proc.h>
+const SZOMB: u32 = 5;
+
+// The macOS side of `Proc::read`, from proc_pidinfo. There are no cgroups,
+// pid namespaces or kernel thread pids here. Memory and cpu need the task
+// port, which other users' processes don't hand out, so those read as zero.
+pub fn read_proc(pid: Pid) -> Option<Proc> {
+    let info: BSDInfo = pidinfo(pid as i32, 0).ok()?;
+    let task: Option<TaskInfo> = pidinfo(pid as i32, 0).ok();
+
+    let name = match c_string(&info.pbi_name) {
+        name if !name.is_empty() => name,
+        _ => c_string(&info.pbi_comm),
+    };
+
+    Some(Proc {
+        pid,
+        ppid: info.pbi_ppid,
+        name,
+        cmdline: pidpath(pid as i32).unwrap_or_default(),
+        uid: info.pbi_uid,
+        state: match info.pbi_status {
+            SZOMB => ProcState::Zombie,
+            _ => ProcState::Running,
+        },
+        kthread: false,
+        rss: task.as_ref().map_or(0, |t| t.pti_resident_size),
+        cpu_time: task.as_ref().map_or(time::Duration::ZERO, |t| {
+            ticks_to_duration(t.pti_total_user + t.pti_total_system)
+        }),
+        cpu_percent: 0.0,
+        start_time: time::Duration::new(info.pbi_start_tvsec, info.pbi_start_tvusec as u32 * 1000),
+        cgroups: Vec::new(),
+        pid_ns: None,
+    })
+}
+
+// Task times are in mach absolute time units. They happen to be nanoseconds
+// on intel, on apple silicon a tick is about 41ns.
+fn ticks_to_duration(ticks: u64) -> time::Duration {
+    let mut timebase = libc::mach_timebase_info { numer: 0, denom: 0 };
+    // libc would rather we used the mach2 crate for this one call
+    #[allow(deprecated)]
+    let ok = unsafe { libc::mach_timebase_info(&mut timebase) } == 0;
+    if !ok || timebase.denom == 0 {
+        return time::Duration::from_nanos(ticks);
+    }
+    let nanos = ticks as u128 * timebase.numer as u128 / timebase.denom as u128;
+    time::Duration::from_nanos(nanos as u64)
+}
+
+fn c_string(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
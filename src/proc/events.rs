@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time;
+
+use super::{Pid, Proc};
+
+// the process table, keyed and ordered by pid
+pub type ProcTable = BTreeMap<Pid, Proc>;
+
+// What changed in the process table between two snapshots. The pid thread
+// works these out so the game only ever touches the processes that changed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ProcEvent {
+    Spawned(Proc),
+    Exited(Pid),
+    Changed(Proc), // same process, different usage, name or state
+}
+
+impl ProcEvent {
+    // Apply the event to `table`.
+    pub fn apply(&self, table: &mut ProcTable) {
+        match self {
+            ProcEvent::Spawned(p) | ProcEvent::Changed(p) => {
+                table.insert(p.pid, p.clone());
+            }
+            ProcEvent::Exited(pid) => {
+                table.remove(pid);
+            }
+        }
+    }
+}
+
+pub fn to_table(procs: Vec<Proc>) -> ProcTable {
+    procs.into_iter().map(|p| (p.pid, p)).collect()
+}
+
+// Bring `table` up to date with a fresh snapshot taken `elapsed` after the
+// last one and return what changed. A reused pid has a different start time
+// and counts as an exit followed by a spawn, so the old process's asteroid
+// doesn't live on as the new one.
+pub fn diff(table: &mut ProcTable, snapshot: Vec<Proc>, elapsed: time::Duration) -> Vec<ProcEvent> {
+    let mut next = to_table(snapshot);
+    let mut events = Vec::new();
+
+    for (pid, old) in table.iter() {
+        match next.get_mut(pid) {
+            Some(new) if new.start_time == old.start_time => {
+                let used = new.cpu_time.saturating_sub(old.cpu_time);
+                new.cpu_percent = 100.0 * used.as_secs_f64() / elapsed.as_secs_f64().max(0.001);
+                if new != old {
+                    events.push(ProcEvent::Changed(new.clone()));
+                }
+            }
+            Some(new) => {
+                events.push(ProcEvent::Exited(*pid));
+                events.push(ProcEvent::Spawned(new.clone()));
+            }
+            None => events.push(ProcEvent::Exited(*pid)),
+        }
+    }
+
+    for (pid, new) in next.iter() {
+        if !table.contains_key(pid) {
+            events.push(ProcEvent::Spawned(new.clone()));
+        }
+    }
+
+    *table = next;
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: time::Duration = time::Duration::from_secs(1);
+
+    fn pids(events: &[ProcEvent]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| match e {
+                ProcEvent::Spawned(p) => format!("+{}", p.pid),
+                ProcEvent::Exited(pid) => format!("-{}", pid),
+                ProcEvent::Changed(p) => format!("~{}", p.pid),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spawn() {
+        let a = Proc::fake(10, 1, "a");
+        let b = Proc::fake(11, 1, "b");
+        let mut t = to_table(vec![a.clone()]);
+
+        let events = diff(&mut t, vec![a, b.clone()], SECOND);
+        assert_eq!(pids(&events), vec!["+11"]);
+        assert_eq!(t.get(&11), Some(&b));
+    }
+
+    #[test]
+    fn exit() {
+        let a = Proc::fake(10, 1, "a");
+        let b = Proc::fake(11, 1, "b");
+        let mut t = to_table(vec![a.clone(), b]);
+
+        let events = diff(&mut t, vec![a], SECOND);
+        assert_eq!(pids(&events), vec!["-11"]);
+        assert!(!t.contains_key(&11));
+    }
+
+    #[test]
+    fn change() {
+        let a = Proc::fake(10, 1, "a");
+        let mut renamed = a.clone();
+        renamed.name = "b".to_string();
+        let mut t = to_table(vec![a]);
+
+        let events = diff(&mut t, vec![renamed.clone()], SECOND);
+        assert_eq!(pids(&events), vec!["~10"]);
+        assert_eq!(t[&10].name, "b");
+    }
+
+    #[test]
+    fn no_change() {
+        let a = Proc::fake(10, 1, "a");
+        let b = Proc::fake(11, 1, "b");
+        let mut t = to_table(vec![a.clone(), b.clone()]);
+
+        assert!(diff(&mut t, vec![a, b], SECOND).is_empty());
+        assert_eq!(t.len(), 2);
+    }
+
+    #[test]
+    fn cpu_percent_is_worked_out_from_cpu_time() {
+        let a = Proc::fake(10, 1, "a");
+        let mut busy = a.clone();
+        busy.cpu_time = time::Duration::from_millis(500);
+        let mut t = to_table(vec![a]);
+
+        let events = diff(&mut t, vec![busy], 2 * SECOND);
+        assert_eq!(pids(&events), vec!["~10"]);
+        assert_eq!(t[&10].cpu_percent, 25.0);
+
+        // the same usage again is only a change because the percentage drops
+        let idle = t[&10].clone();
+        let events = diff(&mut t, vec![idle], SECOND);
+        assert_eq!(pids(&events), vec!["~10"]);
+        assert_eq!(t[&10].cpu_percent, 0.0);
+        let still = t[&10].clone();
+        assert!(diff(&mut t, vec![still], SECOND).is_empty());
+    }
+
+    #[test]
+    fn reused_pid_is_an_exit_and_a_spawn() {
+        let old = Proc::fake(10, 1, "old");
+        let mut new = Proc::fake(10, 1, "new");
+        new.start_time = 5 * SECOND;
+        let mut t = to_table(vec![old]);
+
+        let events = diff(&mut t, vec![new], SECOND);
+        assert_eq!(pids(&events), vec!["-10", "+10"]);
+        assert_eq!(t[&10].name, "new");
+
+        // applying the events to a copy of the old table gets the same result
+        let mut copy = to_table(vec![Proc::fake(10, 1, "old")]);
+        for e in &events {
+            e.apply(&mut copy);
+        }
+        assert_eq!(copy, t);
+    }
+}
@@ -0,0 +1,288 @@
+use libc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::process;
+
+use super::scope::{self, Scope};
+use super::{Pid, Proc};
+
+// Picks processes out by name, for the protected and confirm lists.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RuleSpec", into = "RuleSpec")]
+pub enum NameRule {
+    Name(String),   // exact process name
+    Pattern(Regex), // matched anywhere in the process name
+}
+
+impl NameRule {
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            NameRule::Name(n) => n == name,
+            NameRule::Pattern(re) => re.is_match(name),
+        }
+    }
+}
+
+// how a NameRule is saved, with the pattern as plain text
+#[derive(Serialize, Deserialize)]
+enum RuleSpec {
+    Name(String),
+    Pattern(String),
+}
+
+impl From<NameRule> for RuleSpec {
+    fn from(rule: NameRule) -> RuleSpec {
+        match rule {
+            NameRule::Name(n) => RuleSpec::Name(n),
+            NameRule::Pattern(re) => RuleSpec::Pattern(re.as_str().to_string()),
+        }
+    }
+}
+
+impl TryFrom<RuleSpec> for NameRule {
+    type Error = regex::Error;
+
+    fn try_from(spec: RuleSpec) -> Result<NameRule, regex::Error> {
+        match spec {
+            RuleSpec::Name(n) => Ok(NameRule::Name(n)),
+            RuleSpec::Pattern(p) => Regex::new(&p).map(NameRule::Pattern),
+        }
+    }
+}
+
+// Decides which processes are fair game. Anything excluded here never
+// becomes an asteroid and is refused by `ProcManager::kill_pid`.
+pub struct ProcFilter {
+    own_pid: Pid,
+    parent_pid: Pid,
+    uid: u32,
+    pub allow_all: bool,
+    pub protected: Vec<NameRule>,
+    pub scope: Vec<Scope>,
+}
+
+impl Default for ProcFilter {
+    fn default() -> ProcFilter {
+        ProcFilter {
+            own_pid: process::id(),
+            parent_pid: unsafe { libc::getppid() } as Pid,
+            uid: unsafe { libc::getuid() },
+            allow_all: false,
+            protected: Vec::new(),
+            scope: Vec::new(),
+        }
+    }
+}
+
+impl ProcFilter {
+    pub fn new(protected: Vec<NameRule>) -> ProcFilter {
+        ProcFilter {
+            protected,
+            ..ProcFilter::default()
+        }
+    }
+
+    // for process tables that were filtered before they got to us, e.g. a
+    // replayed recording
+    pub fn allow_all() -> ProcFilter {
+        ProcFilter {
+            allow_all: true,
+            ..ProcFilter::default()
+        }
+    }
+
+    pub fn allows(&self, proc: &Proc) -> bool {
+        if self.allow_all {
+            return true;
+        }
+
+        let pid = proc.pid;
+        if pid <= 1 || pid == self.own_pid || pid == self.parent_pid {
+            return false;
+        }
+
+        // kthreadd is pid 2 and the parent of every other kernel thread
+        if pid == 2 || proc.ppid == 2 || proc.kthread {
+            return false;
+        }
+
+        // only our own processes unless a user was picked explicitly
+        let other_user = self.scope.iter().any(|s| matches!(s, Scope::User(_)));
+        if !other_user && proc.uid != self.uid {
+            return false;
+        }
+
+        if !self.scope.iter().all(|scope| scope.contains(proc)) {
+            return false;
+        }
+
+        !self.protected.iter().any(|rule| rule.matches(&proc.name))
+    }
+
+    pub fn apply(&self, procs: Vec<Proc>) -> Vec<Proc> {
+        let trees = scope::within_trees(&self.scope, &procs);
+        procs
+            .into_iter()
+            .filter(|p| trees.as_ref().is_none_or(|t| t.contains(&p.pid)))
+            .filter(|p| self.allows(p))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn other_uid() -> u32 {
+        unsafe { libc::getuid() + 1 }
+    }
+
+    // an ordinary pid, checked not to be the test's own
+    const PID: Pid = 1000;
+
+    fn with(pid: Pid, f: impl Fn(&mut Proc)) -> Proc {
+        let mut p = Proc::fake(pid, 1, "worker");
+        f(&mut p);
+        p
+    }
+
+    #[test]
+    fn exclusions() {
+        let own = process::id();
+        let parent = unsafe { libc::getppid() } as Pid;
+        assert!(!(PID..PID + 4).contains(&own) && !(PID..PID + 4).contains(&parent));
+
+        let filter = ProcFilter::new(vec![
+            NameRule::Name("sshd".to_string()),
+            NameRule::Pattern(Regex::new("^systemd").unwrap()),
+        ]);
+
+        let cases = [
+            ("ordinary process", with(PID, |_| {}), true),
+            ("init", with(1, |_| {}), false),
+            ("kthreadd", with(2, |p| p.ppid = 0), false),
+            ("the game itself", with(own, |_| {}), false),
+            ("whatever started the game", with(parent, |_| {}), false),
+            ("child of kthreadd", with(PID, |p| p.ppid = 2), false),
+            ("PF_KTHREAD", with(PID, |p| p.kthread = true), false),
+            ("another user's", with(PID, |p| p.uid = other_uid()), false),
+            (
+                "protected name",
+                with(PID, |p| p.name = "sshd".into()),
+                false,
+            ),
+            (
+                "name containing a protected one",
+                with(PID, |p| p.name = "sshd2".into()),
+                true,
+            ),
+            (
+                "protected pattern",
+                with(PID, |p| p.name = "systemd-logind".into()),
+                false,
+            ),
+        ];
+
+        for (what, proc, allowed) in cases.iter() {
+            assert_eq!(filter.allows(proc), *allowed, "{}", what);
+        }
+    }
+
+    #[test]
+    fn only_a_user_scope_lifts_the_uid_rule() {
+        let theirs = with(PID, |p| p.uid = other_uid());
+        let mut filter = ProcFilter::new(Vec::new());
+
+        filter.scope = vec![Scope::Name(Regex::new("worker").unwrap())];
+        assert!(!filter.allows(&theirs));
+        filter.scope = vec![Scope::Cgroup("/".to_string())];
+        assert!(!filter.allows(&theirs));
+
+        filter.scope = vec![Scope::User(other_uid())];
+        assert!(filter.allows(&theirs));
+        // the scope picks the user, it doesn't open up everyone else's
+        assert!(!filter.allows(&with(PID, |p| p.uid = other_uid() + 1)));
+    }
+
+    #[test]
+    fn user_scope_keeps_the_other_exclusions() {
+        let mut filter = ProcFilter::new(vec![NameRule::Name("sshd".to_string())]);
+        filter.scope = vec![Scope::User(other_uid())];
+
+        let theirs = |f: fn(&mut Proc)| {
+            with(PID, |p| {
+                p.uid = other_uid();
+                f(p)
+            })
+        };
+        assert!(!filter.allows(&theirs(|p| p.kthread = true)));
+        assert!(!filter.allows(&theirs(|p| p.ppid = 2)));
+        assert!(!filter.allows(&theirs(|p| p.name = "sshd".into())));
+        assert!(!filter.allows(&theirs(|p| p.pid = 1)));
+    }
+
+    #[test]
+    fn apply_keeps_what_allows_does() {
+        let filter = ProcFilter::new(vec![NameRule::Name("sshd".to_string())]);
+        let procs = vec![
+            with(PID, |_| {}),
+            with(PID + 1, |p| p.name = "sshd".into()),
+            with(PID + 2, |p| p.kthread = true),
+            with(PID + 3, |_| {}),
+        ];
+
+        let kept: Vec<Pid> = filter.apply(procs).iter().map(|p| p.pid).collect();
+        assert_eq!(kept, vec![PID, PID + 3]);
+    }
+
+    #[test]
+    fn allow_all_lets_everything_through() {
+        let filter = ProcFilter::allow_all();
+        assert!(filter.allows(&with(1, |_| {})));
+        assert!(filter.allows(&with(PID, |p| p.uid = other_uid())));
+    }
+
+    #[test]
+    fn trees_go_through_filtered_out_processes() {
+        let mut filter = ProcFilter::new(vec![NameRule::Name("sshd".to_string())]);
+        filter.scope = vec![Scope::DescendantOf(PID)];
+        let procs = vec![
+            with(PID, |_| {}),
+            with(PID + 1, |p| {
+                p.ppid = PID;
+                p.name = "sshd".into();
+            }),
+            with(PID + 2, |p| p.ppid = PID + 1),
+            with(PID + 3, |p| p.ppid = PID + 2),
+            with(PID + 4, |_| {}),
+        ];
+
+        let kept: Vec<Pid> = filter.apply(procs).iter().map(|p| p.pid).collect();
+        assert_eq!(kept, vec![PID + 2, PID + 3]);
+    }
+
+    #[test]
+    fn every_scope_has_to_match() {
+        let mut filter = ProcFilter::new(Vec::new());
+        filter.scope = vec![
+            Scope::User(other_uid()),
+            Scope::Name(Regex::new("^worker").unwrap()),
+        ];
+        let procs = vec![
+            with(PID, |p| p.uid = other_uid()),
+            with(PID + 1, |p| {
+                p.uid = other_uid();
+                p.name = "shell".into();
+            }),
+            with(PID + 2, |_| {}),
+            with(PID + 3, |p| {
+                p.uid = other_uid();
+                p.name = "worker2".into();
+            }),
+        ];
+
+        let kept: Vec<Pid> = filter.apply(procs).iter().map(|p| p.pid).collect();
+        assert_eq!(kept, vec![PID, PID + 3]);
+    }
+}
@@ -0,0 +1,349 @@
+use libc;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time;
+
+use super::procfs;
+use super::Pid;
+
+#[derive(Copy, Clone)]
+pub struct KillPolicy {
+    // how long a process gets to exit after SIGTERM before it is SIGKILLed
+    pub grace: time::Duration,
+}
+
+impl Default for KillPolicy {
+    fn default() -> KillPolicy {
+        KillPolicy {
+            grace: time::Duration::from_millis(2000),
+        }
+    }
+}
+
+// Whether shooting an asteroid signals the real process. Dry run is the
+// default, live fire has to be asked for explicitly at startup.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum KillMode {
+    #[default]
+    DryRun,
+    LiveFire,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Signal {
+    Term,
+    Kill,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum KillOutcome {
+    Terminated,       // exited after SIGTERM
+    Killed,           // had to be SIGKILLed after the grace period
+    PermissionDenied, // EPERM
+    NoSuchProcess,    // ESRCH, already gone before we got to it
+    Failed(i32),      // any other errno
+    Protected,        // refused by the ProcFilter, never signalled
+}
+
+impl KillOutcome {
+    // did the shot actually take the process down
+    pub fn took(&self) -> bool {
+        matches!(self, KillOutcome::Terminated | KillOutcome::Killed)
+    }
+}
+
+// The process to kill. The start time tells it apart from whatever gets the
+// pid next if it exits before the signal arrives.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct KillTarget {
+    pub pid: Pid,
+    pub start_time: time::Duration, // as in Proc
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct KillReport {
+    pub pid: Pid,
+    pub outcome: KillOutcome,
+}
+
+// Where kills actually go. The kill thread owns one of these, so tests can
+// swap in a fake instead of signalling the OS.
+pub trait KillBackend: Send {
+    fn signal(&mut self, pid: Pid, sig: Signal) -> Result<(), KillOutcome>;
+    fn is_alive(&mut self, pid: Pid) -> bool;
+
+    // whether `pid` still belongs to the process that started at
+    // `start_time`, checked before every signal
+    fn is_same(&mut self, _target: &KillTarget) -> bool {
+        true
+    }
+}
+
+// delivers real signals with kill(2)
+pub struct SignalBackend;
+
+impl KillBackend for SignalBackend {
+    fn signal(&mut self, pid: Pid, sig: Signal) -> Result<(), KillOutcome> {
+        let sig = match sig {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+        };
+        send_signal(pid, sig)
+    }
+
+    // zombies still answer signal 0, so on linux also check the process state
+    fn is_alive(&mut self, pid: Pid) -> bool {
+        if send_signal(pid, 0) == Err(KillOutcome::NoSuchProcess) {
+            return false;
+        }
+
+        match procfs::read_stat(pid) {
+            Some(stat) => stat.state != 'Z',
+            None => !cfg!(target_os = "linux"),
+        }
+    }
+
+    // only linux can tell, elsewhere the pid is trusted
+    fn is_same(&mut self, target: &KillTarget) -> bool {
+        match procfs::read_start_time(target.pid) {
+            Some(start_time) => start_time == target.start_time,
+            None => !cfg!(target_os = "linux"),
+        }
+    }
+}
+
+// logs what would have been signalled and pretends the process went away
+pub struct DryRunBackend;
+
+impl KillBackend for DryRunBackend {
+    fn signal(&mut self, pid: Pid, sig: Signal) -> Result<(), KillOutcome> {
+        println!("DRY RUN: would send {:?} to {}", sig, pid);
+        Ok(())
+    }
+
+    fn is_alive(&mut self, _pid: Pid) -> bool {
+        false
+    }
+}
+
+// remembers every signal it is asked to send, and every process it signals
+// dies straight away
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingBackend {
+    pub sent: std::sync::Arc<std::sync::Mutex<Vec<(Pid, Signal)>>>,
+}
+
+#[cfg(test)]
+impl KillBackend for RecordingBackend {
+    fn signal(&mut self, pid: Pid, sig: Signal) -> Result<(), KillOutcome> {
+        self.sent.lock().unwrap().push((pid, sig));
+        Ok(())
+    }
+
+    fn is_alive(&mut self, _pid: Pid) -> bool {
+        false
+    }
+}
+
+pub fn backend_for(mode: KillMode) -> Box<dyn KillBackend> {
+    match mode {
+        KillMode::DryRun => Box::new(DryRunBackend),
+        KillMode::LiveFire => Box::new(SignalBackend),
+    }
+}
+
+fn send_signal(pid: Pid, sig: libc::c_int) -> Result<(), KillOutcome> {
+    if unsafe { libc::kill(pid as libc::pid_t, sig) } == 0 {
+        return Ok(());
+    }
+
+    match io::Error::last_os_error().raw_os_error() {
+        Some(libc::EPERM) => Err(KillOutcome::PermissionDenied),
+        Some(libc::ESRCH) => Err(KillOutcome::NoSuchProcess),
+        Some(errno) => Err(KillOutcome::Failed(errno)),
+        None => Err(KillOutcome::Failed(0)),
+    }
+}
+
+// a process that has been sent SIGTERM and is waiting out its grace period
+pub struct PendingKill {
+    pub target: KillTarget,
+    deadline: time::Instant,
+}
+
+// Send SIGTERM to `target` at `now`. Returns the pending kill to follow up
+// on, or the final outcome if the signal could not be delivered. A pid that
+// has been reused since the snapshot counts as already gone.
+pub fn terminate(
+    backend: &mut dyn KillBackend,
+    target: KillTarget,
+    policy: &KillPolicy,
+    now: time::Instant,
+) -> Result<PendingKill, KillReport> {
+    let pid = target.pid;
+    if !backend.is_same(&target) {
+        return Err(KillReport {
+            pid,
+            outcome: KillOutcome::NoSuchProcess,
+        });
+    }
+
+    match backend.signal(pid, Signal::Term) {
+        Ok(()) => Ok(PendingKill {
+            target,
+            deadline: now + policy.grace,
+        }),
+        Err(outcome) => Err(KillReport { pid, outcome }),
+    }
+}
+
+impl PendingKill {
+    // Check on a terminated process, escalating to SIGKILL once the grace
+    // period is over. Returns None while the process is still within grace.
+    // If the pid was reused in the meantime ours has exited, and the new
+    // process is left alone.
+    pub fn poll(&self, backend: &mut dyn KillBackend, now: time::Instant) -> Option<KillReport> {
+        let pid = self.target.pid;
+
+        if !backend.is_alive(pid) || !backend.is_same(&self.target) {
+            return Some(KillReport {
+                pid,
+                outcome: KillOutcome::Terminated,
+            });
+        }
+
+        if now < self.deadline {
+            return None;
+        }
+
+        let outcome = match backend.signal(pid, Signal::Kill) {
+            Ok(()) => KillOutcome::Killed,
+            // exited between the check and the SIGKILL
+            Err(KillOutcome::NoSuchProcess) => KillOutcome::Terminated,
+            Err(outcome) => outcome,
+        };
+
+        Some(KillReport { pid, outcome })
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::process::{Child, Command};
+    use std::thread;
+
+    fn spawn(cmd: &str, args: &[&str]) -> (Child, KillTarget) {
+        let child = Command::new(cmd).args(args).spawn().unwrap();
+        let pid = child.id();
+        let target = KillTarget {
+            pid,
+            start_time: procfs::read_start_time(pid).unwrap(),
+        };
+        (child, target)
+    }
+
+    // poll the way the kill thread does until there is an outcome
+    fn finish(pending: PendingKill, backend: &mut dyn KillBackend) -> KillReport {
+        let give_up = time::Instant::now() + time::Duration::from_secs(10);
+        loop {
+            if let Some(report) = pending.poll(backend, time::Instant::now()) {
+                return report;
+            }
+            assert!(time::Instant::now() < give_up, "kill never finished");
+            thread::sleep(time::Duration::from_millis(10));
+        }
+    }
+
+    fn policy(grace_ms: u64) -> KillPolicy {
+        KillPolicy {
+            grace: time::Duration::from_millis(grace_ms),
+        }
+    }
+
+    #[test]
+    fn sleep_exits_on_sigterm() {
+        let (mut child, target) = spawn("sleep", &["60"]);
+        let mut backend = SignalBackend;
+
+        let pending = terminate(&mut backend, target, &policy(5000), time::Instant::now())
+            .ok()
+            .unwrap();
+        let report = finish(pending, &mut backend);
+
+        assert_eq!(report.pid, target.pid);
+        assert_eq!(report.outcome, KillOutcome::Terminated);
+        assert!(!child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn ignoring_sigterm_gets_sigkill_after_grace() {
+        // the ignored disposition survives the exec, so sleep itself is deaf
+        // to SIGTERM and nothing is left behind
+        let (mut child, target) = spawn("sh", &["-c", "trap '' TERM; exec sleep 60"]);
+        let give_up = time::Instant::now() + time::Duration::from_secs(5);
+        while procfs::read_stat(target.pid).unwrap().comm != "sleep" {
+            assert!(time::Instant::now() < give_up, "sh never exec'd sleep");
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        let mut backend = SignalBackend;
+
+        let pending = terminate(&mut backend, target, &policy(200), time::Instant::now())
+            .ok()
+            .unwrap();
+        let report = finish(pending, &mut backend);
+
+        assert_eq!(report.outcome, KillOutcome::Killed);
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn reaped_pid_is_no_such_process() {
+        let (mut child, target) = spawn("sleep", &["0"]);
+        child.wait().unwrap();
+        let mut backend = SignalBackend;
+
+        assert_eq!(
+            backend.signal(target.pid, Signal::Term),
+            Err(KillOutcome::NoSuchProcess)
+        );
+        let report = terminate(&mut backend, target, &policy(200), time::Instant::now())
+            .err()
+            .unwrap();
+        assert_eq!(report.outcome, KillOutcome::NoSuchProcess);
+    }
+
+    #[test]
+    fn dry_run_never_signals() {
+        let (mut child, target) = spawn("sleep", &["60"]);
+        let mut backend = backend_for(KillMode::DryRun);
+
+        let pending = terminate(backend.as_mut(), target, &policy(200), time::Instant::now())
+            .ok()
+            .unwrap();
+        let report = finish(pending, backend.as_mut());
+
+        assert!(report.outcome.took());
+        assert!(SignalBackend.is_alive(target.pid));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn reused_pid_is_left_alone() {
+        let (mut child, mut target) = spawn("sleep", &["60"]);
+        target.start_time += time::Duration::from_secs(1);
+        let mut backend = SignalBackend;
+
+        let report = terminate(&mut backend, target, &policy(200), time::Instant::now())
+            .err()
+            .unwrap();
+        assert_eq!(report.outcome, KillOutcome::NoSuchProcess);
+        assert!(backend.is_alive(target.pid));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
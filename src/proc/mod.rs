@@ -1,103 +1,540 @@
 use libproc::libproc::proc_pid;
-use std::process::Command;
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
-use std::{thread, time};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time;
+
+#[cfg(target_os = "macos")]
+mod darwin;
+mod events;
+mod filter;
+mod kill;
+// only read_stat's liveness checks are used off linux, and they find nothing
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+mod procfs;
+mod scenario;
+mod scope;
+mod stepped;
+mod worker;
+
+pub use self::events::{ProcEvent, ProcTable};
+pub use self::filter::{NameRule, ProcFilter};
+pub use self::kill::{KillBackend, KillMode, KillOutcome, KillPolicy, KillReport, KillTarget};
+#[cfg(test)]
+pub use self::kill::{RecordingBackend, Signal};
+pub use self::scenario::{Scenario, ScenarioSource};
+pub use self::scope::Scope;
+use self::stepped::Stepped;
+use self::worker::Workers;
 
 const LISTPID_INTERVAL: time::Duration = time::Duration::from_millis(1000);
 const KILL_INTERVAL: time::Duration = time::Duration::from_millis(500);
 
-// pub struct Proc {
-//     pid: u32,
-// }
+pub type Pid = u32;
 
-pub type Proc = u32;
-pub fn get_procs() -> Vec<Proc> {
-    if let Ok(pids) = proc_pid::listpids(proc_pid::ProcType::ProcAllPIDS) {
-        println!("Found {} processes using listpids()", pids.len());
-        return pids;
-    } else {
-        return Vec::new();
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ProcState {
+    Running,
+    Sleeping,
+    DiskSleep,
+    Stopped,
+    Zombie,
+    Idle,
+    Other(char),
+}
+
+impl ProcState {
+    pub fn from_char(c: char) -> ProcState {
+        match c {
+            'R' => ProcState::Running,
+            'S' => ProcState::Sleeping,
+            'D' => ProcState::DiskSleep,
+            'T' | 't' => ProcState::Stopped,
+            'Z' => ProcState::Zombie,
+            'I' => ProcState::Idle,
+            c => ProcState::Other(c),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Proc {
+    pub pid: Pid,
+    pub ppid: Pid,
+    pub name: String,
+    pub cmdline: String,
+    pub uid: u32,
+    pub state: ProcState,
+    pub kthread: bool,
+    pub rss: u64,                 // resident memory in bytes
+    pub cpu_time: time::Duration, // user + system time
+    #[serde(default)]
+    pub cpu_percent: f64, // of one core since the previous snapshot
+    pub start_time: time::Duration, // since boot on linux, the epoch on macos
+    pub cgroups: Vec<String>,
+    pub pid_ns: Option<u64>,
+}
+
+impl Proc {
+    #[cfg(target_os = "linux")]
+    pub fn read(pid: Pid) -> Option<Proc> {
+        procfs::read_proc(pid)
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn read(pid: Pid) -> Option<Proc> {
+        darwin::read_proc(pid)
+    }
+
+    // an ordinary process of ours, for tests to adjust
+    #[cfg(test)]
+    pub fn fake(pid: Pid, ppid: Pid, name: &str) -> Proc {
+        Proc {
+            pid,
+            ppid,
+            name: name.to_string(),
+            cmdline: name.to_string(),
+            uid: unsafe { libc::getuid() },
+            state: ProcState::Sleeping,
+            kthread: false,
+            rss: 0,
+            cpu_time: time::Duration::ZERO,
+            cpu_percent: 0.0,
+            start_time: time::Duration::ZERO,
+            cgroups: vec!["/".to_string()],
+            pid_ns: None,
+        }
+    }
+
+    // one line with enough to recognise the process by
+    pub fn summary(&self) -> String {
+        format!(
+            "{} {} [{}] {:?}, {}MB, {:.1}s cpu",
+            self.pid,
+            self.name,
+            self.cmdline,
+            self.state,
+            self.rss / (1024 * 1024),
+            self.cpu_time.as_secs_f64(),
+        )
+    }
+}
+
+// Where process tables come from. The pid thread, or a Stepped, owns one of
+// these and asks it for a fresh snapshot every LISTPID_INTERVAL.
+pub trait ProcSource: Send {
+    fn procs(&mut self) -> Vec<Proc>;
+}
+
+// the real process table, listed with libproc and read from procfs, or
+// from proc_pidinfo on macos
+pub struct SystemSource;
+
+impl ProcSource for SystemSource {
+    fn procs(&mut self) -> Vec<Proc> {
+        get_procs()
+    }
+}
+
+// the same table every time
+#[cfg(test)]
+pub struct FixedSource(pub Vec<Proc>);
+
+#[cfg(test)]
+impl ProcSource for FixedSource {
+    fn procs(&mut self) -> Vec<Proc> {
+        self.0.clone()
     }
 }
 
+// processes that exit between listing and reading are skipped
+pub fn get_procs() -> Vec<Proc> {
+    proc_pid::listpids(proc_pid::ProcType::ProcAllPIDS)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(Proc::read)
+        .collect()
+}
+
 pub struct ProcManager {
-    pub procs: Vec<Proc>,
-    proc_rx: Receiver<Vec<Proc>>,
-    kill_tx: Sender<u32>,
+    pub procs: ProcTable,
+    proc_rx: Receiver<Vec<ProcEvent>>,
+    kill_tx: Sender<KillTarget>,
+    result_rx: Receiver<KillReport>,
+    filter: Arc<ProcFilter>,
+    refused: Vec<KillReport>, // answered here, without the kill thread
+    workers: Workers,
+    stepped: Option<Stepped>, // stands in for the workers on the game's clock
 }
 
 impl ProcManager {
-    pub fn new(rx: Receiver<Vec<u32>>, tx: Sender<u32>) -> ProcManager {
+    pub fn new(
+        procs: Vec<Proc>,
+        rx: Receiver<Vec<ProcEvent>>,
+        tx: Sender<KillTarget>,
+        result_rx: Receiver<KillReport>,
+        filter: Arc<ProcFilter>,
+    ) -> ProcManager {
         ProcManager {
-            procs: get_procs(),
+            procs: events::to_table(procs),
             proc_rx: rx,
             kill_tx: tx,
+            result_rx,
+            filter,
+            refused: Vec::new(),
+            workers: Workers::new(),
+            stepped: None,
         }
     }
 
-    pub fn poll_pids(&mut self) {
-        if let Ok(procs) = self.proc_rx.try_recv() {
-            println!("GOT NEW PIDS");
-            self.procs = procs;
-        } else {
-            // something
+    // Let `dt` seconds of game time pass for a manager that runs on the
+    // game's clock. Threaded managers keep their own time and ignore this.
+    pub fn advance(&mut self, dt: f64) {
+        if let Some(stepped) = &mut self.stepped {
+            stepped.advance(dt);
         }
     }
 
-    pub fn kill_pid(&mut self, pid: u32) {
-        if self.kill_tx.send(pid).is_ok() {
+    // apply every change the pid thread has found since the last call to
+    // `procs` and return them
+    pub fn poll_pids(&mut self) -> Vec<ProcEvent> {
+        let events: Vec<ProcEvent> = self.proc_rx.try_iter().flatten().collect();
+        for event in &events {
+            event.apply(&mut self.procs);
+        }
+        events
+    }
+
+    pub fn kill_pid(&mut self, pid: Pid) {
+        let proc = self.procs.get(&pid);
+        let allowed = self.filter.allow_all || proc.is_some_and(|p| self.filter.allows(p));
+        if !allowed {
+            self.refused.push(KillReport {
+                pid,
+                outcome: KillOutcome::Protected,
+            });
+            return;
+        }
+
+        // only a replay allows pids missing from the table, and its kills
+        // never reach a backend
+        let target = KillTarget {
+            pid,
+            start_time: proc.map(|p| p.start_time).unwrap_or_default(),
+        };
+        if self.kill_tx.send(target).is_ok() {
             println!("SENT KILL {}", pid)
         } else {
-            // error
+            // the kill thread is gone, so the asteroid has to come back
+            println!("FAILED TO SEND KILL {}: kill thread is gone", pid);
+            self.refused.push(KillReport {
+                pid,
+                outcome: KillOutcome::Failed(0),
+            });
         }
     }
+
+    // drain the outcomes of kills that have finished since the last call
+    pub fn poll_kills(&mut self) -> Vec<KillReport> {
+        let mut reports: Vec<KillReport> = self.refused.drain(..).collect();
+        reports.extend(self.result_rx.try_iter());
+        reports
+    }
+
+    // Threads that died while the game was running, with the reason. Once
+    // one is gone the process table or the kills stop updating.
+    pub fn check_workers(&mut self) -> Vec<String> {
+        self.workers.reap()
+    }
+
+    // Stop the proc threads and wait for them. Kills still waiting out their
+    // grace period are abandoned.
+    pub fn shutdown(&mut self) {
+        self.workers.shutdown();
+    }
+}
+
+impl Drop for ProcManager {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+pub fn get_proc_manager(mode: KillMode, policy: KillPolicy, filter: ProcFilter) -> ProcManager {
+    get_proc_manager_with(
+        Box::new(SystemSource),
+        kill::backend_for(mode),
+        policy,
+        filter,
+    )
+}
+
+// Drive the game from a scenario file instead of the real process table.
+// Kills go to the scenario, never to the OS, whatever the kill mode. The
+// scenario runs on game time, see `get_stepped_proc_manager`.
+pub fn get_scenario_proc_manager(
+    path: &str,
+    policy: KillPolicy,
+    filter: ProcFilter,
+) -> Result<ProcManager, String> {
+    let source = ScenarioSource::new(Scenario::load(path)?);
+    let backend = Box::new(source.kill_backend());
+    Ok(get_stepped_proc_manager(
+        Box::new(source),
+        backend,
+        policy,
+        filter,
+    ))
 }
 
-pub fn get_proc_manager() -> ProcManager {
+// Like `get_proc_manager_with`, but without threads: the source is read and
+// kills are carried out from `ProcManager::advance`, every LISTPID_INTERVAL
+// and `policy.grace` of game time rather than wall clock time.
+pub fn get_stepped_proc_manager(
+    source: Box<dyn ProcSource>,
+    backend: Box<dyn KillBackend>,
+    policy: KillPolicy,
+    filter: ProcFilter,
+) -> ProcManager {
+    let (stepped, mut manager) = Stepped::new(source, backend, policy, filter);
+    manager.stepped = Some(stepped);
+    manager
+}
+
+pub fn get_proc_manager_with(
+    mut source: Box<dyn ProcSource>,
+    mut backend: Box<dyn KillBackend>,
+    policy: KillPolicy,
+    filter: ProcFilter,
+) -> ProcManager {
+    let filter = Arc::new(filter);
     let (tx1, rx1) = mpsc::channel();
 
-    // pid monitoring thread
-    thread::spawn(move || loop {
-        let procs = get_procs();
-        println!("GETTING PIDS");
-        if tx1.send(procs).is_ok() {
-            thread::sleep(LISTPID_INTERVAL);
-        } else {
-            println!("EXITING PID THREAD");
-            break;
+    // the first snapshot is handed over whole, after that the thread only
+    // sends what changed
+    let procs = filter.apply(source.procs());
+    let mut table = events::to_table(procs.clone());
+
+    let mut workers = Workers::new();
+
+    // pid monitoring thread, waits on its stop channel between scans
+    let thread_filter = filter.clone();
+    workers.spawn("pids", move |stop| {
+        let mut last_scan = time::Instant::now();
+        while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(LISTPID_INTERVAL) {
+            let procs = thread_filter.apply(source.procs());
+            let events = events::diff(&mut table, procs, last_scan.elapsed());
+            last_scan = time::Instant::now();
+            if !events.is_empty() && tx1.send(events).is_err() {
+                break;
+            }
         }
     });
 
-    // proc killing thread
-    let (tx2, rx2) = mpsc::channel::<u32>();
-    thread::spawn(move || loop {
-        match rx2.try_recv() {
-            Ok(pid) => {
-                if let Ok(child) = Command::new("echo").arg(pid.to_string()).spawn() {
-                    println!("Child's ID is {}", child.id());
-                } else {
-                    println!("command didn't start");
+    // proc killing thread, wakes up for new kills and every KILL_INTERVAL to
+    // follow up on pending ones
+    let (tx2, rx2) = mpsc::channel::<KillTarget>();
+    let (tx3, rx3) = mpsc::channel::<KillReport>();
+    workers.spawn("kills", move |stop| {
+        let mut pending = Vec::new();
+        loop {
+            match rx2.recv_timeout(KILL_INTERVAL) {
+                Ok(target) => {
+                    match kill::terminate(backend.as_mut(), target, &policy, time::Instant::now()) {
+                        Ok(p) => pending.push(p),
+                        Err(report) => {
+                            let _ = tx3.send(report);
+                        }
+                    }
                 }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
             }
-            Err(TryRecvError::Disconnected) => {
-                println!("Terminating.");
+
+            pending.retain(|p: &kill::PendingKill| {
+                match p.poll(backend.as_mut(), time::Instant::now()) {
+                    Some(report) => {
+                        let _ = tx3.send(report);
+                        false
+                    }
+                    None => true,
+                }
+            });
+
+            if worker::stopped(&stop) {
                 break;
             }
-            Err(TryRecvError::Empty) => {}
         }
 
-        thread::sleep(KILL_INTERVAL);
+        if !pending.is_empty() {
+            println!("abandoning {} pending kills", pending.len());
+        }
     });
 
-    ProcManager::new(rx1, tx2)
+    let mut manager = ProcManager::new(procs, rx1, tx2, rx3, filter);
+    manager.workers = workers;
+    manager
 }
 
-/*
-use std::process::Command;
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn manager() -> (ProcManager, RecordingBackend) {
+        let backend = RecordingBackend::default();
+        let manager = get_proc_manager_with(
+            Box::new(FixedSource(vec![Proc::fake(1000, 1, "victim")])),
+            Box::new(backend.clone()),
+            KillPolicy::default(),
+            ProcFilter::new(Vec::new()),
+        );
+        (manager, backend)
+    }
+
+    // wait for the kill thread to answer
+    fn reports(manager: &mut ProcManager) -> Vec<KillReport> {
+        let give_up = time::Instant::now() + time::Duration::from_secs(5);
+        let mut reports = Vec::new();
+        while reports.is_empty() {
+            assert!(time::Instant::now() < give_up, "kill never came back");
+            std::thread::sleep(time::Duration::from_millis(10));
+            reports = manager.poll_kills();
+        }
+        reports
+    }
+
+    #[test]
+    fn kills_go_through_the_given_backend() {
+        let (mut manager, backend) = manager();
+
+        manager.kill_pid(1000);
+        let reports = reports(&mut manager);
+
+        assert_eq!(*backend.sent.lock().unwrap(), vec![(1000, Signal::Term)]);
+        assert_eq!(reports[0].pid, 1000);
+        assert_eq!(reports[0].outcome, KillOutcome::Terminated);
+    }
+
+    #[test]
+    fn reads_a_child_process() {
+        let mut child = Command::new("sleep").args(["30", "30"]).spawn().unwrap();
+        let give_up = time::Instant::now() + time::Duration::from_secs(5);
+        let mut proc = Proc::read(child.id()).unwrap();
+        while proc.cmdline.is_empty() {
+            assert!(time::Instant::now() < give_up, "sleep never exec'd");
+            std::thread::sleep(time::Duration::from_millis(10));
+            proc = Proc::read(child.id()).unwrap();
+        }
+
+        assert_eq!(proc.pid, child.id());
+        assert_eq!(proc.ppid, std::process::id());
+        assert_eq!(proc.name, "sleep");
+        assert_eq!(proc.cmdline, "sleep 30 30");
+        assert_eq!(proc.uid, unsafe { libc::getuid() });
+        assert!(!proc.kthread);
+        assert!(proc.rss > 0);
+        assert_eq!(Some(proc.start_time), procfs::read_start_time(child.id()));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(Proc::read(child.id()).is_none());
+    }
+
+    #[test]
+    fn protected_pids_never_reach_the_backend() {
+        let (mut manager, backend) = manager();
+
+        manager.kill_pid(1);
+        let reports = reports(&mut manager);
+
+        assert!(backend.sent.lock().unwrap().is_empty());
+        assert_eq!(reports[0].pid, 1);
+        assert_eq!(reports[0].outcome, KillOutcome::Protected);
+    }
 
-Command::new("ls")
-        .arg("-l")
-        .arg("-a")
-        .spawn()
-        .expect("ls command failed to start");
-*/
+    // nothing on the first scan, which happens before the thread starts
+    struct PanicsLater(bool);
+
+    impl ProcSource for PanicsLater {
+        fn procs(&mut self) -> Vec<Proc> {
+            if self.0 {
+                panic!("lost /proc");
+            }
+            self.0 = true;
+            Vec::new()
+        }
+    }
+
+    // keeps a count of who still holds it
+    struct Held {
+        _count: Arc<()>,
+    }
+
+    impl ProcSource for Held {
+        fn procs(&mut self) -> Vec<Proc> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn panicking_source_is_reported() {
+        let mut manager = get_proc_manager_with(
+            Box::new(PanicsLater(false)),
+            Box::new(RecordingBackend::default()),
+            KillPolicy::default(),
+            ProcFilter::new(Vec::new()),
+        );
+
+        let give_up = time::Instant::now() + time::Duration::from_secs(5);
+        let mut gone = Vec::new();
+        while gone.is_empty() {
+            assert!(time::Instant::now() < give_up, "pids thread never died");
+            std::thread::sleep(time::Duration::from_millis(10));
+            gone = manager.check_workers();
+        }
+
+        assert_eq!(gone, vec!["pids thread panicked: lost /proc".to_string()]);
+        // reported once, and the kill thread carries on
+        assert!(manager.check_workers().is_empty());
+    }
+
+    #[test]
+    fn dropping_the_manager_joins_its_threads() {
+        let held = Arc::new(());
+        let backend = RecordingBackend::default();
+        let sent = backend.sent.clone();
+        let manager = get_proc_manager_with(
+            Box::new(Held {
+                _count: held.clone(),
+            }),
+            Box::new(backend),
+            KillPolicy::default(),
+            ProcFilter::new(Vec::new()),
+        );
+        assert_eq!(Arc::strong_count(&held), 2);
+        assert_eq!(Arc::strong_count(&sent), 2);
+
+        // each thread owns one of them and lets go when it finishes
+        drop(manager);
+        assert_eq!(Arc::strong_count(&held), 1);
+        assert_eq!(Arc::strong_count(&sent), 1);
+    }
+
+    #[test]
+    fn shutdown_leaves_nothing_running() {
+        let held = Arc::new(());
+        let mut manager = get_proc_manager_with(
+            Box::new(Held {
+                _count: held.clone(),
+            }),
+            Box::new(RecordingBackend::default()),
+            KillPolicy::default(),
+            ProcFilter::new(Vec::new()),
+        );
+
+        manager.shutdown();
+        assert_eq!(Arc::strong_count(&held), 1);
+        assert!(manager.check_workers().is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use libc;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::time;
+
+use super::{Pid, Proc, ProcState};
+
+// set in /proc/<pid>/stat flags for kernel threads
+const PF_KTHREAD: u32 = 0x0020_0000;
+
+pub struct Stat {
+    pub comm: String,
+    pub state: char,
+    pub ppid: Pid,
+    pub flags: u32,
+    pub utime: u64,     // clock ticks
+    pub stime: u64,     // clock ticks
+    pub starttime: u64, // clock ticks after boot
+    pub rss: u64,       // pages
+}
+
+impl Stat {
+    pub fn is_kthread(&self) -> bool {
+        self.flags & PF_KTHREAD != 0
+    }
+}
+
+// Parse /proc/<pid>/stat. The command name is wrapped in parens and may
+// itself contain spaces or parens, so split on the last ')'.
+pub fn read_stat(pid: Pid) -> Option<Stat> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+
+    let comm = stat[open + 1..close].to_string();
+    // fields[0] is field 3 (state) in proc(5)
+    let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+    let field = |n: usize| -> Option<u64> { fields.get(n - 3)?.parse().ok() };
+
+    Some(Stat {
+        comm,
+        state: fields.first()?.chars().next()?,
+        ppid: field(4)? as Pid,
+        flags: field(9)? as u32,
+        utime: field(14)?,
+        stime: field(15)?,
+        starttime: field(22)?,
+        rss: field(24)?,
+    })
+}
+
+// the owner of /proc/<pid> is the process's effective uid
+pub fn read_uid(pid: Pid) -> Option<u32> {
+    fs::metadata(format!("/proc/{}", pid)).ok().map(|m| m.uid())
+}
+
+// arguments are NUL separated, kernel threads have an empty cmdline
+pub fn read_cmdline(pid: Pid) -> Option<String> {
+    let raw = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args: Vec<String> = raw
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+
+    Some(args.join(" "))
+}
+
+// Every distinct cgroup path the process is in. Lines look like
+// `hierarchy:controllers:path`, on a pure cgroup v2 host there is just the
+// one `0::path` line, on hybrid hosts each v1 controller can differ.
+pub fn read_cgroups(pid: Pid) -> Option<Vec<String>> {
+    let text = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    let mut paths: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if let Some(path) = line.splitn(3, ':').nth(2) {
+            if !paths.iter().any(|p| p == path) {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    Some(paths)
+}
+
+// the inode in the `pid:[4026531836]` link, only readable for processes we
+// could ptrace
+pub fn read_pid_ns(pid: Pid) -> Option<u64> {
+    let link = fs::read_link(format!("/proc/{}/ns/pid", pid)).ok()?;
+    let link = link.to_str()?;
+    link.strip_prefix("pid:[")?.strip_suffix(']')?.parse().ok()
+}
+
+fn ticks_to_duration(ticks: u64) -> time::Duration {
+    let hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64;
+    time::Duration::from_millis(ticks * 1000 / hz)
+}
+
+// when the process currently holding `pid` started, to tell a reused pid
+// from the process we meant
+pub fn read_start_time(pid: Pid) -> Option<time::Duration> {
+    read_stat(pid).map(|stat| ticks_to_duration(stat.starttime))
+}
+
+pub fn read_proc(pid: Pid) -> Option<Proc> {
+    let stat = read_stat(pid)?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+
+    Some(Proc {
+        pid,
+        ppid: stat.ppid,
+        cmdline: read_cmdline(pid).unwrap_or_default(),
+        uid: read_uid(pid)?,
+        state: ProcState::from_char(stat.state),
+        kthread: stat.is_kthread(),
+        rss: stat.rss * page_size,
+        cpu_time: ticks_to_duration(stat.utime + stat.stime),
+        cpu_percent: 0.0,
+        start_time: ticks_to_duration(stat.starttime),
+        cgroups: read_cgroups(pid).unwrap_or_default(),
+        pid_ns: read_pid_ns(pid),
+        name: stat.comm,
+    })
+}
@@ -0,0 +1,165 @@
+use serde::Deserialize;
+use serde_json;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time;
+
+use super::kill::{KillBackend, KillOutcome, Signal};
+use super::{Pid, Proc, ProcSource, ProcState, LISTPID_INTERVAL};
+
+// A made up process table for demos and tests, e.g.
+//
+//   { "procs": [
+//       { "pid": 100, "name": "server", "rss": 600000000 },
+//       { "pid": 101, "ppid": 100, "name": "worker", "appear": 5, "exit": 20 },
+//       { "pid": 102, "name": "daemon", "cpu_secs": 90, "stubborn": true,
+//         "cgroup": "/system.slice/daemon.service" }
+//   ] }
+//
+// `appear` and `exit` are in seconds of scenario time, which moves on by one
+// LISTPID_INTERVAL per snapshot. Scenarios are run on the game's clock, so
+// that is the same as game time. Nothing here is ever really signalled.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scenario {
+    pub procs: Vec<ScenarioProc>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScenarioProc {
+    pub pid: Pid,
+    #[serde(default = "default_ppid")]
+    pub ppid: Pid,
+    pub name: String,
+    #[serde(default)]
+    pub rss: u64,
+    #[serde(default)]
+    pub cpu_secs: f64,
+    #[serde(default)]
+    pub appear: f64,
+    #[serde(default)]
+    pub exit: Option<f64>,
+    #[serde(default = "default_cgroup")]
+    pub cgroup: String,
+    #[serde(default)]
+    pub stubborn: bool, // ignores SIGTERM and has to be SIGKILLed
+}
+
+fn default_ppid() -> Pid {
+    1
+}
+
+fn default_cgroup() -> String {
+    "/".to_string()
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Scenario, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl ScenarioProc {
+    fn alive_at(&self, now: f64) -> bool {
+        self.appear <= now && self.exit.is_none_or(|exit| now < exit)
+    }
+
+    // scenario processes belong to whoever runs the game so the default
+    // filter doesn't throw them all out
+    fn to_proc(&self) -> Proc {
+        Proc {
+            pid: self.pid,
+            ppid: self.ppid,
+            name: self.name.clone(),
+            cmdline: self.name.clone(),
+            uid: unsafe { libc::getuid() },
+            state: ProcState::Running,
+            kthread: false,
+            rss: self.rss,
+            cpu_time: time::Duration::from_secs_f64(self.cpu_secs),
+            cpu_percent: 0.0,
+            start_time: time::Duration::from_secs_f64(self.appear),
+            cgroups: vec![self.cgroup.clone()],
+            pid_ns: None,
+        }
+    }
+}
+
+struct ScenarioState {
+    scenario: Scenario,
+    now: f64,
+    killed: HashSet<Pid>,
+}
+
+impl ScenarioState {
+    fn find_alive(&self, pid: Pid) -> Option<&ScenarioProc> {
+        self.scenario
+            .procs
+            .iter()
+            .find(|p| p.pid == pid && p.alive_at(self.now) && !self.killed.contains(&pid))
+    }
+}
+
+// Plays a scenario back one snapshot at a time.
+pub struct ScenarioSource {
+    state: Arc<Mutex<ScenarioState>>,
+}
+
+impl ScenarioSource {
+    pub fn new(scenario: Scenario) -> ScenarioSource {
+        ScenarioSource {
+            state: Arc::new(Mutex::new(ScenarioState {
+                scenario,
+                now: 0.0,
+                killed: HashSet::new(),
+            })),
+        }
+    }
+
+    // a backend whose kills take the process out of this scenario
+    pub fn kill_backend(&self) -> ScenarioKillBackend {
+        ScenarioKillBackend {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl ProcSource for ScenarioSource {
+    fn procs(&mut self) -> Vec<Proc> {
+        let mut state = self.state.lock().unwrap();
+        let procs = state
+            .scenario
+            .procs
+            .iter()
+            .filter(|p| p.alive_at(state.now) && !state.killed.contains(&p.pid))
+            .map(ScenarioProc::to_proc)
+            .collect();
+        state.now += LISTPID_INTERVAL.as_secs_f64();
+        procs
+    }
+}
+
+pub struct ScenarioKillBackend {
+    state: Arc<Mutex<ScenarioState>>,
+}
+
+impl KillBackend for ScenarioKillBackend {
+    fn signal(&mut self, pid: Pid, sig: Signal) -> Result<(), KillOutcome> {
+        let mut state = self.state.lock().unwrap();
+        let stubborn = match state.find_alive(pid) {
+            Some(p) => p.stubborn,
+            None => return Err(KillOutcome::NoSuchProcess),
+        };
+
+        println!("SCENARIO: {:?} to {}", sig, pid);
+        if sig == Signal::Kill || !stubborn {
+            state.killed.insert(pid);
+        }
+        Ok(())
+    }
+
+    fn is_alive(&mut self, pid: Pid) -> bool {
+        self.state.lock().unwrap().find_alive(pid).is_some()
+    }
+}
@@ -0,0 +1,215 @@
+use libc;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+
+use super::{procfs, Pid, Proc};
+
+// Narrows the asteroid field down to one part of the machine, e.g. a single
+// container or service. A process has to be inside every scope given.
+#[derive(Clone, Debug)]
+pub enum Scope {
+    Cgroup(String),    // this cgroup or anything below it
+    PidNamespace(u64), // namespace inode, as in /proc/<pid>/ns/pid
+    Unit(String),      // a systemd unit, `.service` is implied if no suffix
+    User(u32),         // owned by this uid instead of our own
+    Name(Regex),       // process name matches
+    DescendantOf(Pid), // started by this pid, directly or not
+}
+
+impl Scope {
+    // the pid namespace `pid` lives in, so `--pidns` can take a container's
+    // init pid rather than an inode number
+    pub fn pid_namespace_of(pid: Pid) -> Option<Scope> {
+        procfs::read_pid_ns(pid).map(Scope::PidNamespace)
+    }
+
+    pub fn unit(name: &str) -> Scope {
+        if name.contains('.') {
+            Scope::Unit(name.to_string())
+        } else {
+            Scope::Unit(format!("{}.service", name))
+        }
+    }
+
+    // a uid or a user name from the password database
+    pub fn user(user: &str) -> Option<Scope> {
+        if let Ok(uid) = user.parse() {
+            return Some(Scope::User(uid));
+        }
+
+        let name = CString::new(user).ok()?;
+        let pw = unsafe { libc::getpwnam(name.as_ptr()) };
+        if pw.is_null() {
+            None
+        } else {
+            Some(Scope::User(unsafe { (*pw).pw_uid }))
+        }
+    }
+
+    // Whether `proc` is in scope on its own. DescendantOf depends on the rest
+    // of the table and always passes here, `within_trees` checks it.
+    pub fn contains(&self, proc: &Proc) -> bool {
+        match self {
+            Scope::Cgroup(prefix) => {
+                let prefix = prefix.trim_end_matches('/');
+                proc.cgroups.iter().any(|path| {
+                    path == prefix || prefix.is_empty() || path.starts_with(&format!("{}/", prefix))
+                })
+            }
+            Scope::PidNamespace(ns) => proc.pid_ns == Some(*ns),
+            Scope::Unit(unit) => proc
+                .cgroups
+                .iter()
+                .any(|path| path.split('/').any(|part| part == unit)),
+            Scope::User(uid) => proc.uid == *uid,
+            Scope::Name(re) => re.is_match(&proc.name),
+            Scope::DescendantOf(_) => true,
+        }
+    }
+}
+
+// Keep only the processes below every DescendantOf root. Works on the whole
+// snapshot so a chain through processes that are filtered out for other
+// reasons still counts.
+pub fn within_trees(scope: &[Scope], procs: &[Proc]) -> Option<HashSet<Pid>> {
+    let roots: Vec<Pid> = scope
+        .iter()
+        .filter_map(|s| match s {
+            Scope::DescendantOf(pid) => Some(*pid),
+            _ => None,
+        })
+        .collect();
+    if roots.is_empty() {
+        return None;
+    }
+
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for p in procs {
+        children.entry(p.ppid).or_default().push(p.pid);
+    }
+
+    let mut inside: Option<HashSet<Pid>> = None;
+    for root in roots {
+        let tree = descendants(&children, root);
+        inside = Some(match inside {
+            Some(set) => set.intersection(&tree).cloned().collect(),
+            None => tree,
+        });
+    }
+    inside
+}
+
+fn descendants(children: &HashMap<Pid, Vec<Pid>>, root: Pid) -> HashSet<Pid> {
+    let mut found = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(pid) = stack.pop() {
+        for &child in children.get(&pid).into_iter().flatten() {
+            if found.insert(child) {
+                stack.push(child);
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_cgroup(path: &str) -> Proc {
+        let mut p = Proc::fake(1000, 1, "worker");
+        p.cgroups = vec![path.to_string()];
+        p
+    }
+
+    #[test]
+    fn cgroups_match_whole_path_components() {
+        for prefix in &["/a/b", "/a/b/"] {
+            let scope = Scope::Cgroup(prefix.to_string());
+            assert!(scope.contains(&in_cgroup("/a/b")));
+            assert!(scope.contains(&in_cgroup("/a/b/c")));
+            assert!(!scope.contains(&in_cgroup("/a/bc")));
+            assert!(!scope.contains(&in_cgroup("/a")));
+        }
+    }
+
+    #[test]
+    fn root_cgroup_is_everything() {
+        let scope = Scope::Cgroup("/".to_string());
+        for path in &["/", "/a", "/user.slice/user-1000.slice/session-2.scope"] {
+            assert!(scope.contains(&in_cgroup(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn units_default_to_services() {
+        let foo = Scope::unit("foo");
+        assert!(foo.contains(&in_cgroup("/system.slice/foo.service")));
+        assert!(foo.contains(&in_cgroup("/system.slice/foo.service/worker")));
+        assert!(!foo.contains(&in_cgroup("/system.slice/foo")));
+        assert!(!foo.contains(&in_cgroup("/system.slice/foobar.service")));
+
+        let timer = Scope::unit("foo.timer");
+        assert!(timer.contains(&in_cgroup("/system.slice/foo.timer")));
+        assert!(!timer.contains(&in_cgroup("/system.slice/foo.service")));
+    }
+
+    #[test]
+    fn any_cgroup_can_match() {
+        let mut p = in_cgroup("/other");
+        p.cgroups.push("/system.slice/foo.service".to_string());
+        assert!(Scope::unit("foo").contains(&p));
+        assert!(Scope::Cgroup("/system.slice".to_string()).contains(&p));
+    }
+
+    // 10 ─ 11 ─ 12 ─ 13
+    //         └─ 14
+    // 20 ─ 21
+    fn forest() -> Vec<Proc> {
+        [
+            (10, 1),
+            (11, 10),
+            (12, 11),
+            (13, 12),
+            (14, 11),
+            (20, 1),
+            (21, 20),
+        ]
+        .iter()
+        .map(|&(pid, ppid)| Proc::fake(pid, ppid, "worker"))
+        .collect()
+    }
+
+    fn trees(roots: &[Pid]) -> Vec<Pid> {
+        let scope: Vec<Scope> = roots.iter().map(|&r| Scope::DescendantOf(r)).collect();
+        let mut pids: Vec<Pid> = within_trees(&scope, &forest())
+            .unwrap()
+            .into_iter()
+            .collect();
+        pids.sort();
+        pids
+    }
+
+    #[test]
+    fn no_roots_no_trees() {
+        let scope = [Scope::unit("foo"), Scope::User(0)];
+        assert!(within_trees(&scope, &forest()).is_none());
+    }
+
+    #[test]
+    fn descendants_leave_the_root_out() {
+        assert_eq!(trees(&[10]), vec![11, 12, 13, 14]);
+        assert_eq!(trees(&[12]), vec![13]);
+        assert_eq!(trees(&[13]), Vec::<Pid>::new());
+        assert_eq!(trees(&[99]), Vec::<Pid>::new());
+    }
+
+    #[test]
+    fn several_roots_intersect() {
+        assert_eq!(trees(&[10, 11]), vec![12, 13, 14]);
+        assert_eq!(trees(&[11, 10]), vec![12, 13, 14]);
+        assert_eq!(trees(&[11, 12]), vec![13]);
+        assert_eq!(trees(&[10, 20]), Vec::<Pid>::new());
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time;
+
+use super::events::{self, ProcEvent, ProcTable};
+use super::kill::{self, KillBackend, KillPolicy, KillReport, KillTarget, PendingKill};
+use super::{ProcFilter, ProcManager, ProcSource, LISTPID_INTERVAL};
+
+// Does what the pid and kill threads do, but on the game's clock: it only
+// moves on when `advance` is called from the simulation step. A scenario run
+// this way plays out the same whether a second of game time takes a second
+// or a microsecond, as in a headless run.
+pub struct Stepped {
+    source: Box<dyn ProcSource>,
+    backend: Box<dyn KillBackend>,
+    policy: KillPolicy,
+    filter: Arc<ProcFilter>,
+    table: ProcTable,
+    epoch: time::Instant, // game time zero, for kill deadlines
+    now: time::Duration,  // game time so far
+    next_scan: time::Duration,
+    pending: Vec<PendingKill>,
+    proc_tx: Sender<Vec<ProcEvent>>,
+    kill_rx: Receiver<KillTarget>,
+    result_tx: Sender<KillReport>,
+}
+
+impl Stepped {
+    // Take the first snapshot and hand back a manager for it, talking to
+    // the Stepped through the same channels the threads would use.
+    pub fn new(
+        mut source: Box<dyn ProcSource>,
+        backend: Box<dyn KillBackend>,
+        policy: KillPolicy,
+        filter: ProcFilter,
+    ) -> (Stepped, ProcManager) {
+        let filter = Arc::new(filter);
+        let (proc_tx, proc_rx) = mpsc::channel();
+        let (kill_tx, kill_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+
+        let procs = filter.apply(source.procs());
+        let table = events::to_table(procs.clone());
+        let manager = ProcManager::new(procs, proc_rx, kill_tx, result_rx, filter.clone());
+
+        let stepped = Stepped {
+            source,
+            backend,
+            policy,
+            filter,
+            table,
+            epoch: time::Instant::now(),
+            now: time::Duration::ZERO,
+            next_scan: LISTPID_INTERVAL,
+            pending: Vec::new(),
+            proc_tx,
+            kill_rx,
+            result_tx,
+        };
+        (stepped, manager)
+    }
+
+    // Move game time on by `dt` seconds: start the kills asked for since the
+    // last call, follow up on pending ones and take a snapshot every
+    // LISTPID_INTERVAL. Whatever comes of it is waiting in the channels.
+    pub fn advance(&mut self, dt: f64) {
+        self.now += time::Duration::from_secs_f64(dt);
+        let clock = self.epoch + self.now;
+
+        for target in self.kill_rx.try_iter() {
+            match kill::terminate(self.backend.as_mut(), target, &self.policy, clock) {
+                Ok(p) => self.pending.push(p),
+                Err(report) => {
+                    let _ = self.result_tx.send(report);
+                }
+            }
+        }
+
+        let (backend, result_tx) = (&mut self.backend, &self.result_tx);
+        self.pending
+            .retain(|p| match p.poll(backend.as_mut(), clock) {
+                Some(report) => {
+                    let _ = result_tx.send(report);
+                    false
+                }
+                None => true,
+            });
+
+        while self.now >= self.next_scan {
+            self.next_scan += LISTPID_INTERVAL;
+            let procs = self.filter.apply(self.source.procs());
+            let events = events::diff(&mut self.table, procs, LISTPID_INTERVAL);
+            if !events.is_empty() {
+                let _ = self.proc_tx.send(events);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc::{get_stepped_proc_manager, KillOutcome, Pid, Scenario, ScenarioSource};
+    use serde_json;
+
+    const DT: f64 = 1.0 / 60.0;
+
+    // pids no real process can have, so the filter never mistakes one for
+    // the test itself
+    const SCENARIO: &str = r#"{ "procs": [
+        { "pid": 5000000, "name": "server" },
+        { "pid": 5000001, "name": "late", "appear": 2 },
+        { "pid": 5000002, "name": "brief", "appear": 1, "exit": 3 },
+        { "pid": 5000003, "name": "stubborn", "stubborn": true }
+    ] }"#;
+
+    fn manager(grace_secs: u64) -> ProcManager {
+        let scenario: Scenario = serde_json::from_str(SCENARIO).unwrap();
+        let source = ScenarioSource::new(scenario);
+        let backend = Box::new(source.kill_backend());
+        let policy = KillPolicy {
+            grace: time::Duration::from_secs(grace_secs),
+        };
+        get_stepped_proc_manager(
+            Box::new(source),
+            backend,
+            policy,
+            ProcFilter::new(Vec::new()),
+        )
+    }
+
+    // run for `secs` of game time and collect the kill outcomes
+    fn run(manager: &mut ProcManager, secs: f64) -> Vec<KillReport> {
+        let mut reports = Vec::new();
+        for _ in 0..(secs / DT).round() as u64 {
+            manager.advance(DT);
+            manager.poll_pids();
+            reports.extend(manager.poll_kills());
+        }
+        reports
+    }
+
+    fn pids(manager: &ProcManager) -> Vec<Pid> {
+        manager.procs.keys().cloned().collect()
+    }
+
+    #[test]
+    fn schedule_follows_game_time() {
+        let mut manager = manager(2);
+        assert_eq!(pids(&manager), vec![5000000, 5000003]);
+
+        run(&mut manager, 1.5);
+        assert_eq!(pids(&manager), vec![5000000, 5000002, 5000003]);
+        run(&mut manager, 1.0);
+        assert_eq!(pids(&manager), vec![5000000, 5000001, 5000002, 5000003]);
+        run(&mut manager, 1.0);
+        assert_eq!(pids(&manager), vec![5000000, 5000001, 5000003]);
+    }
+
+    #[test]
+    fn nothing_happens_without_game_time() {
+        let mut manager = manager(2);
+        std::thread::sleep(time::Duration::from_millis(50));
+        assert!(manager.poll_pids().is_empty());
+        assert_eq!(pids(&manager), vec![5000000, 5000003]);
+    }
+
+    #[test]
+    fn kills_take_effect_on_the_next_snapshot() {
+        let mut manager = manager(2);
+        manager.kill_pid(5000000);
+
+        let reports = run(&mut manager, DT);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].outcome, KillOutcome::Terminated);
+        assert!(manager.procs.contains_key(&5000000));
+
+        run(&mut manager, 1.0);
+        assert!(!manager.procs.contains_key(&5000000));
+    }
+
+    #[test]
+    fn stubborn_processes_wait_out_the_grace_period() {
+        let mut manager = manager(2);
+        manager.kill_pid(5000003);
+
+        assert!(run(&mut manager, 1.9).is_empty());
+        let reports = run(&mut manager, 0.2);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].outcome, KillOutcome::Killed);
+
+        run(&mut manager, 1.0);
+        assert!(!manager.procs.contains_key(&5000003));
+    }
+}
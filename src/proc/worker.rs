@@ -0,0 +1,79 @@
+use std::any::Any;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+// The threads behind a ProcManager. Each one is handed a stop receiver and
+// should return soon after it disconnects.
+pub struct Workers {
+    stop: Vec<Sender<()>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    pub fn new() -> Workers {
+        Workers {
+            stop: Vec::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn spawn<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(Receiver<()>) + Send + 'static,
+    {
+        let (stop_tx, stop_rx) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || f(stop_rx))
+            .expect("failed to spawn proc thread");
+        self.stop.push(stop_tx);
+        self.handles.push(handle);
+    }
+
+    // Join every thread that has finished without being asked to and say
+    // why it went away.
+    pub fn reap(&mut self) -> Vec<String> {
+        let (done, running) = self.handles.drain(..).partition(|h| h.is_finished());
+        self.handles = running;
+        done.into_iter().map(join).collect()
+    }
+
+    // Tell every thread to stop and wait for them.
+    pub fn shutdown(&mut self) {
+        self.stop.clear();
+        for handle in self.handles.drain(..) {
+            let name = handle.thread().name().unwrap_or("proc").to_string();
+            let result = handle.join();
+            if result.is_err() {
+                println!("{}", describe(&name, result));
+            }
+        }
+    }
+}
+
+// true once the owner has asked the thread to stop
+pub fn stopped(stop: &Receiver<()>) -> bool {
+    !matches!(stop.try_recv(), Err(TryRecvError::Empty))
+}
+
+fn join(handle: JoinHandle<()>) -> String {
+    let name = handle.thread().name().unwrap_or("proc").to_string();
+    describe(&name, handle.join())
+}
+
+fn describe(name: &str, result: thread::Result<()>) -> String {
+    match result {
+        Ok(()) => format!("{} thread exited", name),
+        Err(payload) => format!("{} thread panicked: {}", name, panic_message(&payload)),
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "unknown panic"
+    }
+}
@@ -7,7 +7,7 @@ use movable_body::*;
 use traits::*;
 use vecmath::Vector2;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Projectile {
     pub c: Collider,
     pub b: MovableBody,
@@ -15,21 +15,20 @@ pub struct Projectile {
 
 const PROJECTILE_RADIUS: f64 = 5.0;
 
-const BLACK: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
 const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
 const PROJECTILE_SPEED: f64 = 200.0;
 const C3: [f32; 4] = [0.5, 0.5, 0.0, 0.4];
 
 impl GameObject for Projectile {
     fn update(&mut self, dt: f64) {
-        if (self.b.state == BodyState::Dead) {
+        if self.b.state == BodyState::Dead {
             return;
         }
         self.b.update(dt);
         self.c.p = self.b.p;
     }
     fn render(&self, ctx: &Context, gl: &mut GlGraphics) {
-        if (self.b.state == BodyState::Dead) {
+        if self.b.state == BodyState::Dead {
             return;
         }
         let square = rectangle::square(0.0, 0.0, 5.0);
@@ -42,7 +41,7 @@ impl GameObject for Projectile {
         rectangle(RED, square, transform, gl);
     }
     fn render_debug(&self, ctx: &Context, gl: &mut GlGraphics) {
-        if (self.b.state == BodyState::Dead) {
+        if self.b.state == BodyState::Dead {
             return;
         }
         let transform = ctx.transform.trans(self.b.p[0], self.b.p[1]);
@@ -59,19 +58,13 @@ impl GameObject for Projectile {
 }
 
 impl Collides for Projectile {
-    fn collides_with<C: Collides>(&self, other: &C) -> bool {
-        self.c.are_colliding(other.get_collider())
-    }
-
     fn get_collider(&self) -> &Collider {
         &self.c
     }
 
     fn on_collision<C: Collides>(&mut self, other: &C) {
-        let t = other.collider_type();
-        match (t) {
-            ColliderType::Asteroid => (), // trigger end game,
-            _ => (),
+        if let ColliderType::Asteroid = other.collider_type() {
+            self.destroy();
         }
     }
 
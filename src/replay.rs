@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::fs;
+use std::iter::Peekable;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::vec;
+
+use asteroid::SizeThresholds;
+use input::GameInput;
+use proc::{KillReport, KillTarget, NameRule, Proc, ProcEvent, ProcFilter, ProcManager};
+
+// Something from outside the simulation that changed its course.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Recorded {
+    Input(GameInput),
+    Procs(Vec<ProcEvent>), // changes to the process table were picked up
+    Kill(KillReport),      // the outcome of a kill came back
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TickEvent {
+    pub tick: u64,
+    pub event: Recorded,
+}
+
+// Everything needed to play a run back exactly: the seed and settings it
+// started with, the process table it started from and every input, process
+// change and kill outcome stamped with the tick it arrived on.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub window_size: [f64; 2],
+    pub sizing: SizeThresholds,
+    #[serde(default)]
+    pub confirm: Vec<NameRule>,
+    #[serde(default)]
+    pub bounce: bool,
+    pub initial_procs: Vec<Proc>,
+    pub events: Vec<TickEvent>,
+}
+
+impl Recording {
+    pub fn load(path: &str) -> Result<Recording, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+// Feeds a recording back in. The process table and kill outcomes go through
+// the same channels the proc threads would use, so the game can't tell the
+// difference, and kills only ever reach the virtual pids in the recording.
+pub struct Replay {
+    events: Peekable<vec::IntoIter<TickEvent>>,
+    proc_tx: Sender<Vec<ProcEvent>>,
+    result_tx: Sender<KillReport>,
+    _kill_rx: Receiver<KillTarget>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> (Replay, ProcManager) {
+        let (proc_tx, proc_rx) = mpsc::channel();
+        let (kill_tx, kill_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+
+        // the recorded tables were already filtered, and refused kills were
+        // recorded like any other outcome
+        let proc_manager = ProcManager::new(
+            recording.initial_procs,
+            proc_rx,
+            kill_tx,
+            result_rx,
+            Arc::new(ProcFilter::allow_all()),
+        );
+
+        let replay = Replay {
+            events: recording.events.into_iter().peekable(),
+            proc_tx,
+            result_tx,
+            _kill_rx: kill_rx,
+        };
+
+        (replay, proc_manager)
+    }
+
+    // Queue up the process changes and kill outcomes recorded for `tick` and
+    // return its inputs.
+    pub fn events_for(&mut self, tick: u64) -> Vec<GameInput> {
+        let mut inputs = Vec::new();
+        while let Some(e) = self.events.next_if(|e| e.tick <= tick) {
+            match e.event {
+                Recorded::Input(input) => inputs.push(input),
+                Recorded::Procs(events) => {
+                    let _ = self.proc_tx.send(events);
+                }
+                Recorded::Kill(report) => {
+                    let _ = self.result_tx.send(report);
+                }
+            }
+        }
+        inputs
+    }
+}
@@ -0,0 +1,689 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashSet;
+
+use asteroid::{AsteroidManager, SizeThresholds};
+use collider::Collider;
+use collision;
+use config::Config;
+use input::GameInput;
+use movable_body::{BodyState, MovableBody};
+use player::{Player, RotationState, PLAYER_SHAPE};
+use proc::{self, NameRule, Pid, ProcEvent, ProcFilter, ProcManager};
+use projectile::ProjectileManager;
+use replay::{Recorded, Recording, Replay, TickEvent};
+use traits::*;
+
+pub const PLAYER_LIVES: u32 = 3;
+const RESPAWN_CLEAR_RADIUS: f64 = 100.0;
+
+// Which screen the game is on. Update, render and input all dispatch on it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Phase {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+    ConfirmKill(Pid), // waiting for the player to ok killing this pid
+}
+
+pub struct GameState {
+    pub phase: Phase,
+    pub player: Player,
+    pub asteroid_manager: AsteroidManager,
+    pub projectile_manager: ProjectileManager,
+    pub proc_manager: ProcManager,
+    pub lives: u32,
+    pub seed: u64,
+    pub rng: StdRng,             // every random decision in the game comes from here
+    pub confirm: Vec<NameRule>,  // processes that need a yes before they die
+    pub confirmed: HashSet<Pid>, // already said yes to, until the pid exits
+    pub confirm_hit: Option<([f64; 2], [f64; 2])>, // prompted asteroid position, hit normal
+}
+
+impl GameState {
+    pub fn new(config: Config, window_size: [f64; 2]) -> Result<GameState, String> {
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let mut filter = ProcFilter::new(config.protected);
+        filter.scope = config.scope;
+        let proc_manager = match &config.scenario {
+            Some(path) => {
+                println!("SCENARIO {}: no real processes will be signalled", path);
+                proc::get_scenario_proc_manager(path, config.kill_policy, filter)?
+            }
+            None => proc::get_proc_manager(config.kill_mode, config.kill_policy, filter),
+        };
+        let mut game = GameState::with_proc_manager(
+            proc_manager,
+            seed,
+            config.sizing,
+            config.confirm,
+            window_size,
+        );
+        game.asteroid_manager.bounce = config.bounce;
+        Ok(game)
+    }
+
+    pub fn with_proc_manager(
+        proc_manager: ProcManager,
+        seed: u64,
+        sizing: SizeThresholds,
+        confirm: Vec<NameRule>,
+        window_size: [f64; 2],
+    ) -> GameState {
+        println!("seed {}", seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let centre = [window_size[0] / 2.0, window_size[1] / 2.0];
+        let player = Player::new(
+            MovableBody::new(centre, window_size),
+            Collider::polygon(centre, PLAYER_SHAPE.to_vec()),
+        );
+
+        let mut asteroid_manager = AsteroidManager::new(window_size, sizing);
+        asteroid_manager.sync_procs(proc_manager.procs.values(), &mut rng);
+
+        GameState {
+            phase: Phase::Title,
+            player,
+            asteroid_manager,
+            projectile_manager: ProjectileManager::new(window_size),
+            proc_manager,
+            lives: PLAYER_LIVES,
+            seed,
+            rng,
+            confirm,
+            confirmed: HashSet::new(),
+            confirm_hit: None,
+        }
+    }
+
+    pub fn needs_confirm(&self, pid: Pid) -> bool {
+        !self.confirmed.contains(&pid)
+            && self
+                .proc_manager
+                .procs
+                .get(&pid)
+                .is_some_and(|p| self.confirm.iter().any(|rule| rule.matches(&p.name)))
+    }
+
+    // start a new round without touching the proc threads: the player is
+    // back in the centre, lives are reset and every asteroid that was shot
+    // down but whose process is still alive comes back
+    pub fn restart(&mut self) {
+        let [w, h] = self.player.b.window_size;
+        self.player.respawn([w / 2.0, h / 2.0]);
+        self.player.set_thrust(false);
+        self.player.set_rotation(RotationState::None);
+        self.lives = PLAYER_LIVES;
+        self.confirmed.clear();
+        self.confirm_hit = None;
+        self.projectile_manager.projectiles.clear();
+        self.asteroid_manager.asteroids.clear();
+        self.asteroid_manager
+            .sync_procs(self.proc_manager.procs.values(), &mut self.rng);
+        self.phase = Phase::Playing;
+    }
+}
+
+// The game without a window: owns the state and advances it in fixed `dt`
+// steps so the same inputs always land on the same tick.
+pub struct Simulation {
+    pub game: GameState,
+    pub tick: u64,
+    pub dt: f64,
+    recording: Option<Recording>,
+    replay: Option<Replay>,
+}
+
+impl Simulation {
+    pub fn new(game: GameState, dt: f64) -> Simulation {
+        Simulation {
+            game,
+            tick: 0,
+            dt,
+            recording: None,
+            replay: None,
+        }
+    }
+
+    // Build the simulation the config asks for: a replay of a recording or a
+    // new game, recording from the start if asked to.
+    pub fn from_config(
+        config: Config,
+        window_size: [f64; 2],
+        dt: f64,
+    ) -> Result<Simulation, String> {
+        let record = config.record.is_some();
+        let mut sim = match &config.replay {
+            Some(path) => Simulation::replay(Recording::load(path)?, dt),
+            None => Simulation::new(GameState::new(config, window_size)?, dt),
+        };
+        if record {
+            sim.start_recording();
+        }
+        Ok(sim)
+    }
+
+    // Play a recording back instead of watching real processes. Inputs from
+    // `handle_input` are ignored, only the recorded ones are applied.
+    pub fn replay(recording: Recording, dt: f64) -> Simulation {
+        let (seed, sizing, window_size) = (recording.seed, recording.sizing, recording.window_size);
+        let confirm = recording.confirm.clone();
+        let bounce = recording.bounce;
+        let (replay, proc_manager) = Replay::new(recording);
+        let mut game =
+            GameState::with_proc_manager(proc_manager, seed, sizing, confirm, window_size);
+        game.asteroid_manager.bounce = bounce;
+
+        let mut sim = Simulation::new(game, dt);
+        sim.replay = Some(replay);
+        sim
+    }
+
+    // Start logging everything from outside the simulation so the run can
+    // be replayed. Must be called before the first step.
+    pub fn start_recording(&mut self) {
+        let game = &self.game;
+        self.recording = Some(Recording {
+            seed: game.seed,
+            window_size: game.player.b.window_size,
+            sizing: game.asteroid_manager.sizing,
+            confirm: game.confirm.clone(),
+            bounce: game.asteroid_manager.bounce,
+            initial_procs: game.proc_manager.procs.values().cloned().collect(),
+            events: Vec::new(),
+        });
+    }
+
+    pub fn save_recording(&self, path: &str) {
+        if let Some(recording) = &self.recording {
+            match recording.save(path) {
+                Ok(()) => println!("recorded {} events to {}", recording.events.len(), path),
+                Err(e) => println!("failed to save recording: {}", e),
+            }
+        }
+    }
+
+    pub fn step(&mut self) {
+        if let Some(replay) = &mut self.replay {
+            for input in replay.events_for(self.tick) {
+                self.apply_input(input);
+            }
+        }
+
+        let dt = self.dt;
+        let game = &mut self.game;
+        let mut received = Vec::new();
+        match game.phase {
+            Phase::Playing => update_playing(game, dt, &mut received),
+            // the field keeps drifting and tracking processes behind menus
+            Phase::Title | Phase::GameOver => update_field(game, dt, &mut received),
+            Phase::Paused | Phase::ConfirmKill(_) => {}
+        }
+        self.log(received);
+        self.tick += 1;
+    }
+
+    pub fn handle_input(&mut self, input: GameInput) {
+        if self.replay.is_none() {
+            self.log(vec![Recorded::Input(input)]);
+            self.apply_input(input);
+        }
+    }
+
+    fn log(&mut self, events: Vec<Recorded>) {
+        if let Some(recording) = &mut self.recording {
+            let tick = self.tick;
+            recording
+                .events
+                .extend(events.into_iter().map(|event| TickEvent { tick, event }));
+        }
+    }
+
+    fn apply_input(&mut self, input: GameInput) {
+        let game = &mut self.game;
+        match (game.phase, input) {
+            (Phase::Playing, GameInput::Thrust(on)) => game.player.set_thrust(on),
+            (Phase::Playing, GameInput::Rotate(r)) => game.player.set_rotation(r),
+            (Phase::Playing, GameInput::Fire) => {
+                game.player.shoot_projectile(&mut game.projectile_manager)
+            }
+            (Phase::Playing, GameInput::Pause) => game.phase = Phase::Paused,
+            (Phase::Title, GameInput::Start) | (Phase::GameOver, GameInput::Start) => {
+                game.restart()
+            }
+            (Phase::Paused, GameInput::Pause) => game.phase = Phase::Playing,
+            (Phase::Paused, GameInput::Restart) => game.restart(),
+            (Phase::ConfirmKill(pid), GameInput::Confirm(yes)) => {
+                if let Some((at, normal)) = game.confirm_hit.take() {
+                    collision::confirm_kill(game, pid, at, normal, yes);
+                }
+                game.phase = Phase::Playing;
+            }
+            _ => {}
+        }
+    }
+
+    // one line summary for the headless runner
+    pub fn summary(&self) -> String {
+        let game = &self.game;
+        let alive = game
+            .asteroid_manager
+            .asteroids
+            .iter()
+            .filter(|a| a.b.state == BodyState::Live)
+            .count();
+
+        format!(
+            "seed={} tick={} phase={:?} lives={} player=[{:.1}, {:.1}] asteroids={}/{} projectiles={}",
+            game.seed,
+            self.tick,
+            game.phase,
+            game.lives,
+            game.player.b.p[0],
+            game.player.b.p[1],
+            alive,
+            game.asteroid_manager.asteroids.len(),
+            game.projectile_manager.projectiles.len(),
+        )
+    }
+}
+
+// `received` collects whatever came in from the proc threads this step.
+fn update_playing(game: &mut GameState, dt: f64, received: &mut Vec<Recorded>) {
+    game.projectile_manager.purge();
+
+    game.player.update(dt);
+
+    for projectile in game.projectile_manager.projectiles.iter_mut() {
+        projectile.update(dt);
+    }
+
+    update_field(game, dt, received);
+
+    let events = collision::detect_collisions(game);
+    collision::resolve_collisions(game, events);
+
+    // a prompt raised this step waits for its answer before game over
+    if game.player.b.state == BodyState::Dead && game.phase == Phase::Playing {
+        if game.lives == 0 {
+            println!("GAME OVER");
+            game.phase = Phase::GameOver;
+        } else {
+            // respawn at the centre once nothing is in the way
+            let [w, h] = game.player.b.window_size;
+            let centre = [w / 2.0, h / 2.0];
+            if game.asteroid_manager.is_clear(centre, RESPAWN_CLEAR_RADIUS) {
+                game.player.respawn(centre);
+            }
+        }
+    }
+}
+
+fn update_field(game: &mut GameState, dt: f64, received: &mut Vec<Recorded>) {
+    game.asteroid_manager.update(dt);
+    if game.asteroid_manager.bounce {
+        collision::bounce_asteroids(&mut game.asteroid_manager.asteroids);
+    }
+
+    game.proc_manager.advance(dt);
+
+    for failure in game.proc_manager.check_workers() {
+        println!("PROC THREAD DIED: {}", failure);
+    }
+
+    for report in game.proc_manager.poll_kills() {
+        received.push(Recorded::Kill(report));
+        if report.outcome.took() {
+            println!("KILLED {} ({:?})", report.pid, report.outcome);
+        } else {
+            // the shot didn't take, bring the asteroid back
+            println!("FAILED TO KILL {} ({:?})", report.pid, report.outcome);
+            game.asteroid_manager.revive_pid(report.pid);
+        }
+    }
+
+    let events = game.proc_manager.poll_pids();
+    if !events.is_empty() {
+        // a yes is for that process, not whatever gets its pid next
+        for event in &events {
+            if let ProcEvent::Exited(pid) = event {
+                game.confirmed.remove(pid);
+            }
+        }
+        game.asteroid_manager.apply_events(&events, &mut game.rng);
+        received.push(Recorded::Procs(events));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asteroid::Asteroid;
+    use headless;
+    use player::RotationState;
+    use proc::{FixedSource, KillPolicy, Proc, RecordingBackend, Signal};
+    use std::sync::{Arc, Mutex};
+    use std::{thread, time};
+
+    const DT: f64 = 1.0 / 60.0;
+    const WINDOW: [f64; 2] = [800.0, 800.0];
+    const CENTRE: [f64; 2] = [400.0, 400.0];
+
+    // five processes of ours, with pids no real process can have
+    fn game(seed: u64) -> GameState {
+        let procs = (0..5)
+            .map(|i| {
+                let mut p = Proc::fake(5_000_000 + i, 1, "worker");
+                p.rss = i as u64 * 200 * 1024 * 1024;
+                p
+            })
+            .collect();
+        let proc_manager = proc::get_proc_manager_with(
+            Box::new(FixedSource(procs)),
+            Box::new(RecordingBackend::default()),
+            KillPolicy::default(),
+            ProcFilter::new(Vec::new()),
+        );
+        GameState::with_proc_manager(
+            proc_manager,
+            seed,
+            SizeThresholds::default(),
+            Vec::new(),
+            WINDOW,
+        )
+    }
+
+    fn run(seed: u64, script: Vec<(u64, GameInput)>, ticks: u64) -> Simulation {
+        let mut sim = Simulation::new(game(seed), DT);
+        headless::drive(&mut sim, script, ticks);
+        sim
+    }
+
+    fn live_asteroids(sim: &Simulation) -> usize {
+        sim.game
+            .asteroid_manager
+            .asteroids
+            .iter()
+            .filter(|a| a.b.state == BodyState::Live)
+            .count()
+    }
+
+    #[test]
+    fn title_screen_waits_for_start() {
+        let sim = run(1, Vec::new(), 120);
+        assert_eq!(sim.game.phase, Phase::Title);
+        assert_eq!(sim.game.lives, PLAYER_LIVES);
+        assert_eq!(live_asteroids(&sim), 5);
+    }
+
+    #[test]
+    fn thrust_moves_the_player() {
+        // half a second of thrust straight down the screen, then coasting
+        let script = vec![
+            (0, GameInput::Start),
+            (1, GameInput::Thrust(true)),
+            (31, GameInput::Thrust(false)),
+        ];
+        let sim = run(1, script, 61);
+
+        let p = sim.game.player.b.p;
+        assert_eq!(sim.game.phase, Phase::Playing);
+        assert_eq!(sim.game.lives, PLAYER_LIVES);
+        assert_eq!(p[0], CENTRE[0]);
+        assert!((p[1] - 437.5).abs() < 1.0, "player at {:?}", p);
+        assert_eq!(live_asteroids(&sim), 5);
+    }
+
+    #[test]
+    fn pause_freezes_the_game() {
+        let script = vec![
+            (0, GameInput::Start),
+            (1, GameInput::Thrust(true)),
+            (9, GameInput::Pause),
+        ];
+        let mut sim = run(1, script, 10);
+        let player = sim.game.player.b.p;
+        let asteroid = sim.game.asteroid_manager.asteroids[0].b.p;
+
+        headless::drive(&mut sim, Vec::new(), 100);
+        assert_eq!(sim.game.phase, Phase::Paused);
+        assert_eq!(sim.game.player.b.p, player);
+        assert_eq!(sim.game.asteroid_manager.asteroids[0].b.p, asteroid);
+    }
+
+    #[test]
+    fn restart_starts_a_fresh_round() {
+        let mut sim = run(1, vec![(0, GameInput::Start)], 10);
+        let game = &mut sim.game;
+        game.player.destroy();
+        game.lives = 0;
+        game.asteroid_manager.asteroids[0].b.state = BodyState::Dead;
+        game.projectile_manager
+            .spawn_projectile([100.0, 100.0], 0.0);
+
+        sim.handle_input(GameInput::Pause);
+        sim.handle_input(GameInput::Restart);
+
+        let game = &sim.game;
+        assert_eq!(game.phase, Phase::Playing);
+        assert_eq!(game.lives, PLAYER_LIVES);
+        assert!(game.player.b.state == BodyState::Live);
+        assert_eq!(game.player.b.p, CENTRE);
+        assert!(game.projectile_manager.projectiles.is_empty());
+        assert_eq!(live_asteroids(&sim), 5);
+    }
+
+    #[test]
+    fn asteroids_cost_lives_until_game_over() {
+        let mut sim = run(1, vec![(0, GameInput::Start)], 1);
+        // keep the rest of the field out of the way
+        for asteroid in sim.game.asteroid_manager.asteroids.iter_mut() {
+            asteroid.b.p = [0.0, 0.0];
+            asteroid.b.v = [0.0, 0.0];
+        }
+        // wait out the invulnerability after spawning
+        headless::drive(&mut sim, Vec::new(), 200);
+        sim.game.lives = 2;
+
+        for lives in [1, 0] {
+            let asteroid = &mut sim.game.asteroid_manager.asteroids[0];
+            asteroid.b.p = sim.game.player.b.p;
+            sim.step();
+            assert_eq!(sim.game.lives, lives);
+            assert!(sim.game.player.b.state == BodyState::Dead);
+
+            // move it away again so the player can respawn
+            let asteroid = &mut sim.game.asteroid_manager.asteroids[0];
+            asteroid.b.p = [0.0, 0.0];
+            let until = sim.tick + 200;
+            headless::drive(&mut sim, Vec::new(), until);
+        }
+
+        assert_eq!(sim.game.phase, Phase::GameOver);
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let script = || {
+            vec![
+                (0, GameInput::Start),
+                (5, GameInput::Rotate(RotationState::Positive)),
+                (20, GameInput::Thrust(true)),
+                (25, GameInput::Fire),
+                (40, GameInput::Rotate(RotationState::None)),
+                (60, GameInput::Fire),
+                (90, GameInput::Thrust(false)),
+            ]
+        };
+        let positions = |sim: &Simulation| -> Vec<[f64; 2]> {
+            let asteroids = sim.game.asteroid_manager.asteroids.iter();
+            asteroids.map(|a| a.b.p).collect()
+        };
+
+        let a = run(7, script(), 600);
+        let b = run(7, script(), 600);
+        assert_eq!(a.summary(), b.summary());
+        assert_eq!(a.game.player.b.p, b.game.player.b.p);
+        assert_eq!(positions(&a), positions(&b));
+
+        let c = run(8, script(), 600);
+        assert_ne!(positions(&a), positions(&c));
+    }
+
+    #[test]
+    fn replay_matches_the_recorded_run() {
+        let script = vec![
+            (0, GameInput::Start),
+            (10, GameInput::Rotate(RotationState::Negative)),
+            (30, GameInput::Fire),
+            (45, GameInput::Thrust(true)),
+            (50, GameInput::Fire),
+            (70, GameInput::Rotate(RotationState::None)),
+            (80, GameInput::Fire),
+            (120, GameInput::Pause),
+            (150, GameInput::Pause),
+        ];
+        let mut recorded = Simulation::new(game(3), DT);
+        recorded.start_recording();
+        headless::drive(&mut recorded, script, 600);
+        let recording = recorded.recording.clone().unwrap();
+
+        // anything handed to a replay is ignored
+        let mut replayed = Simulation::replay(recording, DT);
+        headless::drive(&mut replayed, vec![(100, GameInput::Thrust(true))], 600);
+
+        assert_eq!(replayed.summary(), recorded.summary());
+        assert_eq!(replayed.game.player.b.p, recorded.game.player.b.p);
+        let positions = |sim: &Simulation| -> Vec<[f64; 2]> {
+            let asteroids = sim.game.asteroid_manager.asteroids.iter();
+            asteroids.map(|a| a.b.p).collect()
+        };
+        assert_eq!(positions(&replayed), positions(&recorded));
+    }
+
+    const BASH: Pid = 5_000_000;
+
+    // what the recording backend was asked to send
+    type Sent = Arc<Mutex<Vec<(Pid, Signal)>>>;
+
+    // A game past the spawn invulnerability with a bash on the confirm list,
+    // everything at rest and the bash asteroid right in front of the player
+    fn confirm_game() -> (Simulation, Sent) {
+        let backend = RecordingBackend::default();
+        let sent = backend.sent.clone();
+        let proc_manager = proc::get_proc_manager_with(
+            Box::new(FixedSource(vec![
+                Proc::fake(BASH, 1, "bash"),
+                Proc::fake(BASH + 1, 1, "worker"),
+            ])),
+            Box::new(backend),
+            KillPolicy::default(),
+            ProcFilter::new(Vec::new()),
+        );
+        let game = GameState::with_proc_manager(
+            proc_manager,
+            1,
+            SizeThresholds::default(),
+            vec![NameRule::Name("bash".to_string())],
+            WINDOW,
+        );
+
+        let mut sim = Simulation::new(game, DT);
+        headless::drive(&mut sim, vec![(0, GameInput::Start)], 200);
+        for asteroid in sim.game.asteroid_manager.asteroids.iter_mut() {
+            asteroid.b.p = if asteroid.id == BASH {
+                [CENTRE[0], CENTRE[1] + 100.0]
+            } else {
+                [50.0, 50.0]
+            };
+            asteroid.b.v = [0.0, 0.0];
+        }
+        (sim, sent)
+    }
+
+    fn bash(sim: &Simulation) -> &Asteroid {
+        let asteroids = &sim.game.asteroid_manager.asteroids;
+        asteroids.iter().find(|a| a.id == BASH).unwrap()
+    }
+
+    // fire straight ahead and wait for the prompt
+    fn shoot_bash(sim: &mut Simulation) {
+        sim.handle_input(GameInput::Fire);
+        for _ in 0..60 {
+            sim.step();
+        }
+        assert_eq!(sim.game.phase, Phase::ConfirmKill(BASH));
+        assert!(sim.game.confirm_hit.is_some());
+        assert!(bash(sim).b.state == BodyState::Live);
+    }
+
+    #[test]
+    fn no_spares_the_process() {
+        let (mut sim, sent) = confirm_game();
+        shoot_bash(&mut sim);
+
+        // it was drifting into the shot and bounces back off it
+        sim.game
+            .asteroid_manager
+            .asteroids
+            .iter_mut()
+            .find(|a| a.id == BASH)
+            .unwrap()
+            .b
+            .v = [0.0, -30.0];
+        sim.handle_input(GameInput::Confirm(false));
+        assert_eq!(sim.game.phase, Phase::Playing);
+        let v = bash(&sim).b.v;
+        assert!(v[1] > 0.0);
+        assert!((vecmath::vec2_len(v) - 30.0).abs() < 1e-9);
+
+        let until = sim.tick + 30;
+        headless::drive(&mut sim, Vec::new(), until);
+        thread::sleep(time::Duration::from_millis(100));
+        assert!(bash(&sim).b.state == BodyState::Live);
+        assert!(sent.lock().unwrap().is_empty());
+        assert!(sim.game.confirmed.is_empty());
+    }
+
+    #[test]
+    fn yes_kills_the_process() {
+        let (mut sim, sent) = confirm_game();
+        shoot_bash(&mut sim);
+
+        sim.handle_input(GameInput::Confirm(true));
+        assert_eq!(sim.game.phase, Phase::Playing);
+        assert!(bash(&sim).b.state == BodyState::Dead);
+        assert!(sim.game.confirmed.contains(&BASH));
+
+        let give_up = time::Instant::now() + time::Duration::from_secs(5);
+        while sent.lock().unwrap().is_empty() {
+            assert!(time::Instant::now() < give_up, "kill never sent");
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        assert_eq!(*sent.lock().unwrap(), vec![(BASH, Signal::Term)]);
+
+        // a new round asks again
+        sim.handle_input(GameInput::Pause);
+        sim.handle_input(GameInput::Restart);
+        assert!(sim.game.confirmed.is_empty());
+    }
+
+    #[test]
+    fn prompt_outlasts_losing_the_last_life() {
+        let (mut sim, _) = confirm_game();
+        sim.game.lives = 1;
+        sim.game.asteroid_manager.asteroids[1].b.p = sim.game.player.b.p;
+        let bash_at = bash(&sim).b.p;
+        sim.game.projectile_manager.spawn_projectile(bash_at, 0.0);
+
+        sim.step();
+        assert_eq!(sim.game.lives, 0);
+        assert_eq!(sim.game.phase, Phase::ConfirmKill(BASH));
+
+        sim.handle_input(GameInput::Confirm(false));
+        sim.step();
+        assert_eq!(sim.game.phase, Phase::GameOver);
+    }
+}
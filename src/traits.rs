@@ -1,8 +1,9 @@
 use graphics::Context;
 use opengl_graphics::GlGraphics;
 
-use collider::Collider;
+use collider::{Collider, Contact};
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum ColliderType {
     Asteroid,
     Projectile,
@@ -10,14 +11,16 @@ pub enum ColliderType {
 }
 
 pub trait Collides {
-    fn collides_with<C: Collides>(&self, other: &C) -> bool;
     fn get_collider(&self) -> &Collider;
+    fn contact_with<C: Collides>(&self, other: &C) -> Option<Contact> {
+        self.get_collider().contact(other.get_collider())
+    }
     fn on_collision<C: Collides>(&mut self, other: &C);
     fn collider_type(&self) -> ColliderType;
 }
 
 pub trait GameObject {
     fn render(&self, ctxt: &Context, gl: &mut GlGraphics);
-    fn render_debug(&self, ctx: &Context, gl: &mut GlGraphics) {}
+    fn render_debug(&self, _ctx: &Context, _gl: &mut GlGraphics) {}
     fn update(&mut self, _: f64) {}
 }